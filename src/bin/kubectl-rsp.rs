@@ -0,0 +1,6 @@
+use rsp_cli::{KubectlPlugin, RspError};
+
+fn main() -> Result<(), RspError> {
+    let plugin = KubectlPlugin::new();
+    plugin.run()
+}
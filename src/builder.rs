@@ -0,0 +1,175 @@
+use crate::error::RspError;
+use crate::peeler::Peeler;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Builds a ConfigMap manifest from files on disk, mirroring
+/// `kubectl create configmap --from-file`.
+pub struct ConfigMapBuilder {
+    name: String,
+    namespace: Option<String>,
+    labels: BTreeMap<String, String>,
+    annotations: BTreeMap<String, String>,
+    sources: Vec<String>,
+}
+
+impl ConfigMapBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            namespace: None,
+            labels: BTreeMap::new(),
+            annotations: BTreeMap::new(),
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn annotation(mut self, key: &str, value: &str) -> Self {
+        self.annotations.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds a source in `--from-file` syntax: either `PATH` (a file, or a
+    /// directory whose regular files are all added) or `KEY=PATH`.
+    pub fn source(mut self, source: &str) -> Self {
+        self.sources.push(source.to_string());
+        self
+    }
+
+    pub fn build(&self) -> Result<Value, RspError> {
+        let mut data = BTreeMap::new();
+        let mut binary_data = BTreeMap::new();
+
+        for source in &self.sources {
+            for (key, path) in self.expand_source(source)? {
+                if data.contains_key(&key) || binary_data.contains_key(&key) {
+                    return Err(RspError::InvalidFormat(format!(
+                        "duplicate key '{key}' in ConfigMap data"
+                    )));
+                }
+
+                let bytes = fs::read(&path)
+                    .map_err(|_| RspError::FileNotFound(path.display().to_string()))?;
+                match String::from_utf8(bytes) {
+                    Ok(text) => {
+                        data.insert(key, text);
+                    }
+                    Err(err) => {
+                        binary_data.insert(key, STANDARD.encode(err.into_bytes()));
+                    }
+                }
+            }
+        }
+
+        let mut metadata = Mapping::new();
+        metadata.insert(string("name"), string(&self.name));
+        if let Some(namespace) = &self.namespace {
+            metadata.insert(string("namespace"), string(namespace));
+        }
+        if !self.labels.is_empty() {
+            metadata.insert(string("labels"), to_mapping(&self.labels));
+        }
+        if !self.annotations.is_empty() {
+            metadata.insert(string("annotations"), to_mapping(&self.annotations));
+        }
+
+        let mut manifest = Mapping::new();
+        manifest.insert(string("apiVersion"), string("v1"));
+        manifest.insert(string("kind"), string("ConfigMap"));
+        manifest.insert(string("metadata"), Value::Mapping(metadata));
+        if !data.is_empty() {
+            manifest.insert(string("data"), to_mapping(&data));
+        }
+        if !binary_data.is_empty() {
+            manifest.insert(string("binaryData"), to_mapping(&binary_data));
+        }
+
+        Ok(Value::Mapping(manifest))
+    }
+
+    /// Renders the manifest with every multi-line `data` entry already in
+    /// block-scalar form.
+    pub fn render(&self) -> Result<String, RspError> {
+        let manifest = self.build()?;
+        Peeler::new().serialize_yaml_with_pipes(&manifest)
+    }
+
+    fn expand_source(&self, source: &str) -> Result<Vec<(String, PathBuf)>, RspError> {
+        if let Some((key, path)) = source.split_once('=') {
+            validate_key(key)?;
+            return Ok(vec![(key.to_string(), Path::new(path).to_path_buf())]);
+        }
+
+        let path = Path::new(source);
+        if path.is_dir() {
+            let mut entries = Vec::new();
+            for entry in fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                if !entry_path.is_file() {
+                    continue;
+                }
+                let key = file_key(&entry_path)?;
+                entries.push((key, entry_path));
+            }
+            entries.sort();
+            Ok(entries)
+        } else if path.is_file() {
+            Ok(vec![(file_key(path)?, path.to_path_buf())])
+        } else {
+            Err(RspError::FileNotFound(source.to_string()))
+        }
+    }
+}
+
+fn file_key(path: &Path) -> Result<String, RspError> {
+    let key = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| RspError::InvalidFormat(format!("invalid file name: {}", path.display())))?;
+    validate_key(key)?;
+    Ok(key.to_string())
+}
+
+pub(crate) fn validate_key(key: &str) -> Result<(), RspError> {
+    let valid = !key.is_empty()
+        && key != "."
+        && key != ".."
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(RspError::InvalidFormat(format!(
+            "'{key}' is not a valid ConfigMap key"
+        )))
+    }
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
+
+fn to_mapping(entries: &BTreeMap<String, String>) -> Value {
+    Value::Mapping(
+        entries
+            .iter()
+            .map(|(key, value)| (string(key), string(value)))
+            .collect(),
+    )
+}
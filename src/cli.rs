@@ -1,6 +1,22 @@
+use crate::builder::ConfigMapBuilder;
+use crate::config::Config;
+use crate::diff;
+use crate::edit;
 use crate::error::RspError;
+use crate::git;
+use crate::krm;
+use crate::kustomize;
+use crate::merge;
+use crate::options::{AnnotationMode, DocumentFormat, PeelOptions};
 use crate::peeler::Peeler;
-use clap::{Arg, ArgMatches, Command};
+use crate::profiles::Profile;
+use crate::report::PeelReport;
+use crate::rules::TargetRule;
+use crate::selector::Selector;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
 
 pub struct Cli;
 
@@ -20,6 +36,17 @@ impl Cli {
 
         match matches.subcommand() {
             Some(("peel", sub_matches)) => self.handle_peel_command(sub_matches),
+            Some(("build", sub_matches)) => self.handle_build_command(sub_matches),
+            Some(("extract-literals", sub_matches)) => {
+                self.handle_extract_literals_command(sub_matches)
+            }
+            Some(("get", sub_matches)) => self.handle_get_command(sub_matches),
+            Some(("edit", sub_matches)) => self.handle_edit_command(sub_matches),
+            Some(("diff", sub_matches)) => self.handle_diff_command(sub_matches),
+            Some(("textconv", sub_matches)) => self.handle_textconv_command(sub_matches),
+            Some(("krm", sub_matches)) => self.handle_krm_command(sub_matches),
+            Some(("git", sub_matches)) => self.handle_git_command(sub_matches),
+            Some(("merge-driver", sub_matches)) => self.handle_merge_driver_command(sub_matches),
             _ => {
                 eprintln!("No command provided. Use --help for available commands.");
                 Ok(())
@@ -31,6 +58,7 @@ impl Cli {
         Command::new("rsp")
             .about("Raw String Peeler - Convert escaped strings in YAML to readable format")
             .version(env!("CARGO_PKG_VERSION"))
+            .arg(configured_arg())
             .subcommand(
                 Command::new("peel")
                     .about("Peel raw strings from YAML files")
@@ -40,6 +68,55 @@ impl Cli {
                             .required(false)
                             .value_name("FILE"),
                     )
+                    .arg(
+                        Arg::new("output")
+                            .short('o')
+                            .long("output")
+                            .help("Output file (default: stdout)")
+                            .value_name("OUTPUT_FILE"),
+                    )
+                    .args(peel_args()),
+            )
+            .subcommand(
+                Command::new("build")
+                    .about("Build a ConfigMap from files, with embedded files in block style")
+                    .arg(
+                        Arg::new("source")
+                            .help("Files or directories to include, optionally as KEY=PATH")
+                            .required(true)
+                            .num_args(1..)
+                            .value_name("SOURCE"),
+                    )
+                    .arg(
+                        Arg::new("name")
+                            .long("name")
+                            .help("Name of the ConfigMap")
+                            .required(true)
+                            .value_name("NAME"),
+                    )
+                    .arg(
+                        Arg::new("namespace")
+                            .short('n')
+                            .long("namespace")
+                            .help("Namespace of the ConfigMap")
+                            .value_name("NAMESPACE"),
+                    )
+                    .arg(
+                        Arg::new("label")
+                            .short('l')
+                            .long("label")
+                            .help("Label to add, can be repeated")
+                            .action(ArgAction::Append)
+                            .value_name("KEY=VALUE"),
+                    )
+                    .arg(
+                        Arg::new("annotation")
+                            .short('a')
+                            .long("annotation")
+                            .help("Annotation to add, can be repeated")
+                            .action(ArgAction::Append)
+                            .value_name("KEY=VALUE"),
+                    )
                     .arg(
                         Arg::new("output")
                             .short('o')
@@ -48,15 +125,595 @@ impl Cli {
                             .value_name("OUTPUT_FILE"),
                     ),
             )
+            .subcommand(
+                Command::new("extract-literals")
+                    .about("Move long kustomize generator literals into files, generating the same resources")
+                    .arg(
+                        Arg::new("file")
+                            .help("The kustomization file, or its directory")
+                            .default_value(".")
+                            .value_name("FILE"),
+                    )
+                    .arg(
+                        Arg::new("min-length")
+                            .long("min-length")
+                            .help("Move single-line values of at least this many bytes too")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value("80")
+                            .value_name("BYTES"),
+                    )
+                    .arg(
+                        Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("List the files that would be written without changing anything")
+                            .action(ArgAction::SetTrue),
+                    ),
+            )
+            .subcommand(
+                Command::new("get")
+                    .about("Print the unescaped content of one embedded file")
+                    .arg(
+                        Arg::new("file")
+                            .help("The YAML file to read (use - for stdin)")
+                            .required(true)
+                            .value_name("FILE"),
+                    )
+                    .arg(
+                        Arg::new("key")
+                            .help("The data key of the embedded file, e.g. config.json")
+                            .required(true)
+                            .value_name("KEY"),
+                    )
+                    .arg(
+                        Arg::new("name")
+                            .long("name")
+                            .help("Select the ConfigMap by metadata.name")
+                            .value_name("NAME"),
+                    ),
+            )
+            .subcommand(
+                Command::new("edit")
+                    .about("Edit one embedded file in $EDITOR and write it back")
+                    .arg(
+                        Arg::new("file")
+                            .help("The YAML file to edit in place")
+                            .required(true)
+                            .value_name("FILE"),
+                    )
+                    .arg(
+                        Arg::new("key")
+                            .help("The data key of the embedded file, e.g. config.json")
+                            .required(true)
+                            .value_name("KEY"),
+                    )
+                    .arg(
+                        Arg::new("name")
+                            .long("name")
+                            .help("Select the ConfigMap by metadata.name")
+                            .value_name("NAME"),
+                    ),
+            )
+            .subcommand(
+                Command::new("diff")
+                    .about("Show changes to embedded files between two manifests")
+                    .arg(
+                        Arg::new("old")
+                            .help("The old manifest, as a file or a git REV:PATH")
+                            .required(true)
+                            .value_name("OLD"),
+                    )
+                    .arg(
+                        Arg::new("new")
+                            .help("The new manifest, as a file or a git REV:PATH")
+                            .required(true)
+                            .value_name("NEW"),
+                    ),
+            )
+            .subcommand(
+                Command::new("textconv")
+                    .about("Print a peeled manifest for git's diff.<driver>.textconv")
+                    .arg(
+                        Arg::new("file")
+                            .help("The file to convert")
+                            .required(true)
+                            .value_name("FILE"),
+                    ),
+            )
+            .subcommand(
+                Command::new("krm").about(
+                    "Run as a KRM function: peel the items of a ResourceList read from stdin",
+                ),
+            )
+            .subcommand(
+                Command::new("git")
+                    .about("Git integration helpers")
+                    .subcommand_required(true)
+                    .subcommand(
+                        Command::new("install")
+                            .about(
+                                "Set up .gitattributes and git config for the current repository",
+                            )
+                            .arg(
+                                Arg::new("driver")
+                                    .long("driver")
+                                    .help("Name of the git diff driver")
+                                    .default_value("rsp")
+                                    .value_name("NAME"),
+                            )
+                            .arg(
+                                Arg::new("pattern")
+                                    .long("pattern")
+                                    .help("File pattern to attach the driver to, can be repeated")
+                                    .action(ArgAction::Append)
+                                    .default_values(["*.yaml", "*.yml"])
+                                    .value_name("PATTERN"),
+                            ),
+                    ),
+            )
+            .subcommand(
+                Command::new("merge-driver")
+                    .about("Three-way merge for git's merge.<driver>.driver (%O %A %B)")
+                    .arg(
+                        Arg::new("base")
+                            .help("The common ancestor version (%O)")
+                            .required(true)
+                            .value_name("BASE"),
+                    )
+                    .arg(
+                        Arg::new("ours")
+                            .help("The current branch version, overwritten with the result (%A)")
+                            .required(true)
+                            .value_name("OURS"),
+                    )
+                    .arg(
+                        Arg::new("theirs")
+                            .help("The other branch version (%B)")
+                            .required(true)
+                            .value_name("THEIRS"),
+                    )
+                    .arg(
+                        Arg::new("marker-size")
+                            .short('L')
+                            .long("marker-size")
+                            .help("Length of conflict markers (%L)")
+                            .value_parser(clap::value_parser!(usize))
+                            .default_value("7")
+                            .value_name("SIZE"),
+                    ),
+            )
     }
 
     fn handle_peel_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
         let output_file = matches.get_one::<String>("output");
-        let peeler = Peeler::new();
+        let peeler = Peeler::with_options(peel_options(matches)?);
+
+        let report = match matches.get_one::<String>("file") {
+            Some(input_file) if input_file != "-" => peeler.peel_file(input_file, output_file)?,
+            _ => peeler.peel_stdin(output_file)?,
+        };
+        if let Some(file_path) = output_file {
+            println!("Output written to {file_path}");
+        }
+        print_report(matches, &report)
+    }
 
-        match matches.get_one::<String>("file") {
-            Some(input_file) => peeler.peel_file(input_file, output_file),
-            None => peeler.peel_stdin(output_file),
+    fn handle_build_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let name = matches
+            .get_one::<String>("name")
+            .expect("name is a required argument");
+        let mut builder = ConfigMapBuilder::new(name);
+
+        if let Some(namespace) = matches.get_one::<String>("namespace") {
+            builder = builder.namespace(namespace);
+        }
+        for label in matches.get_many::<String>("label").unwrap_or_default() {
+            let (key, value) = parse_key_value(label)?;
+            builder = builder.label(key, value);
+        }
+        for annotation in matches.get_many::<String>("annotation").unwrap_or_default() {
+            let (key, value) = parse_key_value(annotation)?;
+            builder = builder.annotation(key, value);
         }
+        for source in matches.get_many::<String>("source").unwrap_or_default() {
+            builder = builder.source(source);
+        }
+
+        let output = builder.render()?;
+        write_output(matches.get_one::<String>("output"), &output)
     }
+
+    fn handle_extract_literals_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let path = matches
+            .get_one::<String>("file")
+            .expect("file has a default value");
+        let min_length = *matches
+            .get_one::<usize>("min-length")
+            .expect("min-length has a default value");
+
+        let file = kustomize::kustomization_file(Path::new(path))?;
+        let content = read_input(&file.display().to_string())?;
+        let (updated, extracted) = kustomize::extract_literals_in_place(&content, min_length)?;
+        if extracted.is_empty() {
+            println!("No literals to extract.");
+            return Ok(());
+        }
+
+        let dir = file.parent().unwrap_or(Path::new(""));
+        if matches.get_flag("dry-run") {
+            for literal in &extracted {
+                println!("Would write {}", dir.join(&literal.path).display());
+            }
+            return Ok(());
+        }
+
+        kustomize::write_files(dir, &extracted)?;
+        fs::write(&file, updated)?;
+        for literal in &extracted {
+            println!("Wrote {}", dir.join(&literal.path).display());
+        }
+        println!("Updated {}", file.display());
+        Ok(())
+    }
+
+    fn handle_get_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let file = matches
+            .get_one::<String>("file")
+            .expect("file is a required argument");
+        let key = matches
+            .get_one::<String>("key")
+            .expect("key is a required argument");
+        let name = matches.get_one::<String>("name").map(String::as_str);
+
+        let content = read_input(file)?;
+        let embedded =
+            configured_peeler(matches, PeelOptions::new())?.extract(&content, key, name)?;
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(embedded.as_bytes())?;
+        if !embedded.is_empty() && !embedded.ends_with('\n') {
+            stdout.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn handle_edit_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let file = matches
+            .get_one::<String>("file")
+            .expect("file is a required argument");
+        let key = matches
+            .get_one::<String>("key")
+            .expect("key is a required argument");
+        let name = matches.get_one::<String>("name").map(String::as_str);
+
+        let content =
+            fs::read_to_string(file).map_err(|_| RspError::FileNotFound(file.to_string()))?;
+        let peeler = configured_peeler(matches, PeelOptions::new())?;
+        let current = peeler.extract(&content, key, name)?;
+
+        match edit::edit_in_editor(peeler.formats(), &current, key)? {
+            Some(edited) => {
+                let updated = peeler.replace(&content, key, name, &edited)?;
+                fs::write(file, updated)?;
+                println!("Updated {key} in {file}");
+            }
+            None => println!("Edit cancelled, no changes made."),
+        }
+
+        Ok(())
+    }
+
+    fn handle_diff_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let old = matches
+            .get_one::<String>("old")
+            .expect("old is a required argument");
+        let new = matches
+            .get_one::<String>("new")
+            .expect("new is a required argument");
+
+        let old_content = read_source(old)?;
+        let new_content = read_source(new)?;
+        let diffs = diff::diff_manifests(
+            &configured_peeler(matches, PeelOptions::new())?,
+            &old_content,
+            &new_content,
+        )?;
+
+        for embedded_diff in diffs {
+            print!("{embedded_diff}");
+        }
+        Ok(())
+    }
+
+    fn handle_textconv_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let file = matches
+            .get_one::<String>("file")
+            .expect("file is a required argument");
+
+        // git shows nothing useful if textconv fails, so errors are swallowed
+        let input = fs::read(file).unwrap_or_default();
+        // A broken settings file must not break `git diff`.
+        let peeler = configured_peeler(matches, PeelOptions::new()).unwrap_or_default();
+        let output = git::textconv(&peeler, &input);
+        let _ = io::stdout().lock().write_all(&output);
+        Ok(())
+    }
+
+    fn handle_krm_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let input = read_input("-")?;
+        let output = krm::run(configured_options(matches, PeelOptions::new())?, &input)?;
+        print!("{}", output.text);
+
+        if output.errors {
+            Err(RspError::Processing(
+                "the function reported errors in its results".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_merge_driver_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let path = |name: &str| {
+            matches
+                .get_one::<String>(name)
+                .expect("merge driver paths are required arguments")
+        };
+        let marker_size = *matches
+            .get_one::<usize>("marker-size")
+            .expect("marker-size has a default value");
+
+        let base = read_input(path("base"))?;
+        let ours = read_input(path("ours"))?;
+        let theirs = read_input(path("theirs"))?;
+
+        let outcome = merge::merge_manifests(
+            &configured_peeler(matches, PeelOptions::new())?,
+            &base,
+            &ours,
+            &theirs,
+            marker_size,
+        );
+        fs::write(path("ours"), outcome.text)?;
+
+        if outcome.conflicts {
+            Err(RspError::Conflict(path("ours").to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_git_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        if let Some(("install", sub_matches)) = matches.subcommand() {
+            let driver = sub_matches
+                .get_one::<String>("driver")
+                .expect("driver has a default value");
+            let patterns: Vec<String> = sub_matches
+                .get_many::<String>("pattern")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+
+            let actions = git::install(driver, &patterns)?;
+            if actions.is_empty() {
+                println!("Git integration is already installed.");
+            }
+            for action in actions {
+                println!("{action}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `--config` option read by [`configured_options`].
+pub(crate) fn configured_arg() -> Arg {
+    Arg::new("config")
+        .long("config")
+        .help("Settings file (default: the nearest .rsp.toml)")
+        .global(true)
+        .value_name("CONFIG_FILE")
+}
+
+/// Options shared by `rsp peel` and `kubectl-rsp`, read back with
+/// [`peel_options`] and [`print_report`].
+pub(crate) fn peel_args() -> Vec<Arg> {
+    vec![
+        Arg::new("key")
+            .short('k')
+            .long("key")
+            .help("Only peel this data key, can be repeated")
+            .action(ArgAction::Append)
+            .value_name("KEY"),
+        Arg::new("no-unescape")
+            .long("no-unescape")
+            .help("Keep backslash escapes inside embedded files as they are")
+            .action(ArgAction::SetTrue),
+        Arg::new("template")
+            .long("template")
+            .help("Read the input as a Helm chart template, keeping {{ }} directives and everything but the peeled values as they are")
+            .action(ArgAction::SetTrue),
+        Arg::new("path")
+            .long("path")
+            .help("Also peel the strings selected by this path in any document, e.g. '.spec.values' or '.env[?(@.name==\"CONFIG\")].value'; can be repeated")
+            .action(ArgAction::Append)
+            .value_name("SELECTOR"),
+        Arg::new("format")
+            .long("format")
+            .help("Format of the strings selected with --path (default: detected from the key)")
+            .requires("path")
+            .value_name("FORMAT"),
+        Arg::new("profile")
+            .long("profile")
+            .help("Also apply this built-in profile, can be repeated")
+            .value_parser(profile_names())
+            .action(ArgAction::Append)
+            .value_name("PROFILE"),
+        Arg::new("no-profile")
+            .long("no-profile")
+            .help("Do not apply this built-in profile, can be repeated")
+            .value_parser(profile_names())
+            .action(ArgAction::Append)
+            .value_name("PROFILE"),
+        Arg::new("normalize")
+            .long("normalize")
+            .help("Rewrite peeled files in their canonical layout, such as re-indented YAML")
+            .action(ArgAction::SetTrue),
+        Arg::new("strip-dashboard-fields")
+            .long("strip-dashboard-fields")
+            .help("Drop id, version and iteration from dashboards of the grafana profile")
+            .action(ArgAction::SetTrue),
+        Arg::new("annotations")
+            .long("annotations")
+            .help("Pretty-print (peel) or remove (strip) the last-applied-configuration annotation and those given with --annotation")
+            .value_parser(["keep", "peel", "strip"])
+            .value_name("MODE"),
+        Arg::new("annotation")
+            .long("annotation")
+            .help("Another annotation holding JSON, can be repeated")
+            .requires("annotations")
+            .action(ArgAction::Append)
+            .value_name("KEY"),
+        Arg::new("input-format")
+            .long("input-format")
+            .help("Read the input as YAML, JSON or TOML (default: detected from the file name or content)")
+            .value_parser(["auto", "yaml", "json", "toml"])
+            .value_name("FORMAT"),
+        Arg::new("output-format")
+            .long("output-format")
+            .help("Write YAML, JSON or TOML (default: the input format); JSON output pretty-prints embedded JSON")
+            .value_parser(["yaml", "json", "toml"])
+            .value_name("FORMAT"),
+        Arg::new("report")
+            .long("report")
+            .help("Print a report of peeled and skipped keys to stderr")
+            .value_parser(["human", "json"])
+            .value_name("FORMAT"),
+    ]
+}
+
+/// Builds the peel options chosen with [`peel_args`], on top of the
+/// settings file.
+pub(crate) fn peel_options(matches: &ArgMatches) -> Result<PeelOptions, RspError> {
+    let mut options = PeelOptions::new()
+        .unescape(!matches.get_flag("no-unescape"))
+        .template(matches.get_flag("template"));
+    for key in matches.get_many::<String>("key").unwrap_or_default() {
+        options = options.key(key);
+    }
+    let document_format = |flag: &str| match matches.get_one::<String>(flag).map(String::as_str) {
+        Some("yaml") => Some(DocumentFormat::Yaml),
+        Some("json") => Some(DocumentFormat::Json),
+        Some("toml") => Some(DocumentFormat::Toml),
+        _ => None,
+    };
+    if let Some(format) = document_format("input-format") {
+        options = options.input_format(format);
+    }
+    if let Some(format) = document_format("output-format") {
+        options = options.output_format(format);
+    }
+    for path in matches.get_many::<String>("path").unwrap_or_default() {
+        Selector::parse(path)?;
+        let mut rule = TargetRule::new().path(path);
+        if let Some(format) = matches.get_one::<String>("format") {
+            rule = rule.format(format);
+        }
+        options = options.rule(rule);
+    }
+    // Profile, normalize and annotation flags override the settings file,
+    // so they are applied after it.
+    let mut options = configured_options(matches, options)?;
+    match matches.get_one::<String>("annotations").map(String::as_str) {
+        Some("peel") => options = options.annotations(AnnotationMode::Peel),
+        Some("strip") => options = options.annotations(AnnotationMode::Strip),
+        Some(_) => options = options.annotations(AnnotationMode::Keep),
+        None => {}
+    }
+    for key in matches.get_many::<String>("annotation").unwrap_or_default() {
+        options = options.annotation(key);
+    }
+    for (flag, enabled) in [("profile", true), ("no-profile", false)] {
+        for name in matches.get_many::<String>(flag).unwrap_or_default() {
+            options = options.profile(name.parse::<Profile>()?, enabled);
+        }
+    }
+    if matches.get_flag("normalize") {
+        options = options.normalize(true);
+    }
+    if matches.get_flag("strip-dashboard-fields") {
+        options = options.strip_dashboard_fields(true);
+    }
+    Ok(options)
+}
+
+/// Prints `report` to stderr in the format asked for with `--report`.
+pub(crate) fn print_report(matches: &ArgMatches, report: &PeelReport) -> Result<(), RspError> {
+    match matches.get_one::<String>("report").map(String::as_str) {
+        Some("json") => {
+            let json = serde_json::to_string_pretty(report)
+                .map_err(|e| RspError::Processing(format!("Failed to write report: {e}")))?;
+            eprintln!("{json}");
+        }
+        Some(_) => eprint!("{report}"),
+        None => {}
+    }
+    Ok(())
+}
+
+fn profile_names() -> Vec<&'static str> {
+    Profile::ALL.iter().map(|profile| profile.name()).collect()
+}
+
+/// Builds a peeler from `options` and the settings file given with
+/// `--config`, or else the nearest `.rsp.toml` above the current directory.
+fn configured_peeler(matches: &ArgMatches, options: PeelOptions) -> Result<Peeler, RspError> {
+    Ok(Peeler::with_options(configured_options(matches, options)?))
+}
+
+/// Applies `--config`, or the nearest `.rsp.toml`, on top of `options`.
+fn configured_options(matches: &ArgMatches, options: PeelOptions) -> Result<PeelOptions, RspError> {
+    let config = match matches.get_one::<String>("config") {
+        Some(path) => Some(Config::load(Path::new(path))?),
+        None => Config::discover(&std::env::current_dir()?)?.map(|(_, config)| config),
+    };
+    Ok(match config {
+        Some(config) => config.apply(options),
+        None => options,
+    })
+}
+
+fn write_output(output_file: Option<&String>, output: &str) -> Result<(), RspError> {
+    match output_file {
+        Some(file_path) => {
+            fs::write(file_path, output)?;
+            println!("Output written to {file_path}");
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+fn read_source(source: &str) -> Result<String, RspError> {
+    if !Path::new(source).exists() && source.contains(':') {
+        git::show(source)
+    } else {
+        read_input(source)
+    }
+}
+
+fn read_input(path: &str) -> Result<String, RspError> {
+    if path == "-" {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| RspError::Processing(format!("Failed to read from stdin: {e}")))?;
+        Ok(content)
+    } else {
+        fs::read_to_string(path).map_err(|_| RspError::FileNotFound(path.to_string()))
+    }
+}
+
+fn parse_key_value(pair: &str) -> Result<(&str, &str), RspError> {
+    pair.split_once('=')
+        .ok_or_else(|| RspError::InvalidFormat(format!("expected KEY=VALUE, got '{pair}'")))
 }
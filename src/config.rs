@@ -0,0 +1,136 @@
+//! Project settings read from `.rsp.toml`.
+//!
+//! ```toml
+//! # Keep peeling ConfigMap `data` (the default).
+//! builtin_rule = true
+//!
+//! [[rules]]
+//! kind = "Secret"
+//! paths = ["stringData"]
+//!
+//! [[rules]]
+//! api_version = "helm.toolkit.fluxcd.io/v2"
+//! kind = "HelmRelease"
+//! labels = { team = "platform" }
+//! paths = ["spec.valuesContent"]
+//! format = "yaml"
+//!
+//! # Pretty-print `kubectl.kubernetes.io/last-applied-configuration` and
+//! # these annotations as JSON ("peel"), or remove them ("strip").
+//! annotations = "peel"
+//! annotation_keys = ["example.com/settings"]
+//!
+//! # Rewrite peeled files in their canonical layout.
+//! normalize = true
+//!
+//! # Drop `id`, `version` and `iteration` from Grafana dashboards.
+//! strip_dashboard_fields = true
+//!
+//! # Turn built-in profiles on or off.
+//! [profiles]
+//! argocd = false
+//! grafana = true
+//! ```
+
+use crate::error::RspError;
+use crate::options::{AnnotationMode, PeelOptions};
+use crate::profiles::Profile;
+use crate::rules::TargetRule;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the settings file looked up by [`Config::discover`].
+pub const CONFIG_FILE: &str = ".rsp.toml";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default = "default_builtin_rule")]
+    pub builtin_rule: bool,
+    #[serde(default)]
+    pub rules: Vec<TargetRule>,
+    #[serde(default)]
+    pub annotations: Option<AnnotationMode>,
+    #[serde(default)]
+    pub annotation_keys: Vec<String>,
+    #[serde(default)]
+    pub normalize: Option<bool>,
+    #[serde(default)]
+    pub strip_dashboard_fields: Option<bool>,
+    #[serde(default)]
+    pub profiles: BTreeMap<Profile, bool>,
+}
+
+fn default_builtin_rule() -> bool {
+    true
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            builtin_rule: default_builtin_rule(),
+            rules: Vec::new(),
+            annotations: None,
+            annotation_keys: Vec::new(),
+            normalize: None,
+            strip_dashboard_fields: None,
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn from_toml(content: &str) -> Result<Self, RspError> {
+        toml::from_str(content)
+            .map_err(|e| RspError::InvalidFormat(format!("invalid {CONFIG_FILE}: {e}")))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RspError> {
+        let content = fs::read_to_string(path)
+            .map_err(|_| RspError::FileNotFound(path.display().to_string()))?;
+        Self::from_toml(&content).map_err(|e| match e {
+            RspError::InvalidFormat(message) => {
+                RspError::InvalidFormat(format!("{message} ({})", path.display()))
+            }
+            other => other,
+        })
+    }
+
+    /// Finds the nearest `.rsp.toml` in `dir` or one of its ancestors.
+    pub fn discover(dir: &Path) -> Result<Option<(PathBuf, Self)>, RspError> {
+        for ancestor in dir.ancestors() {
+            let candidate = ancestor.join(CONFIG_FILE);
+            if candidate.is_file() {
+                let config = Self::load(&candidate)?;
+                return Ok(Some((candidate, config)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Applies these settings on top of `options`.
+    pub fn apply(&self, mut options: PeelOptions) -> PeelOptions {
+        options = options.builtin_rule(self.builtin_rule);
+        for rule in &self.rules {
+            options = options.rule(rule.clone());
+        }
+        if let Some(mode) = self.annotations {
+            options = options.annotations(mode);
+        }
+        for key in &self.annotation_keys {
+            options = options.annotation(key);
+        }
+        if let Some(enabled) = self.normalize {
+            options = options.normalize(enabled);
+        }
+        if let Some(enabled) = self.strip_dashboard_fields {
+            options = options.strip_dashboard_fields(enabled);
+        }
+        for (profile, enabled) in &self.profiles {
+            options = options.profile(*profile, *enabled);
+        }
+        options
+    }
+}
@@ -0,0 +1,219 @@
+use crate::error::RspError;
+use crate::manifest;
+use crate::peeler::{EmbeddedFile, Peeler};
+use crate::selector::PathSegment;
+use serde_json::Value as JsonValue;
+use std::fmt;
+
+/// A key-level change between two parsed payloads, such as JSON, YAML or
+/// TOML.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructuralChange {
+    Added {
+        path: String,
+        value: JsonValue,
+    },
+    Removed {
+        path: String,
+        value: JsonValue,
+    },
+    Changed {
+        path: String,
+        old: JsonValue,
+        new: JsonValue,
+    },
+}
+
+/// The difference of one embedded file between two manifests. A side is
+/// `None` when the file does not exist there.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedDiff {
+    pub resource: String,
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub structural: Vec<StructuralChange>,
+}
+
+/// Compares the embedded files of two manifests, matching resources by
+/// kind, namespace and name, and files by their path in the resource.
+pub fn diff_manifests(
+    peeler: &Peeler,
+    old: &str,
+    new: &str,
+) -> Result<Vec<EmbeddedDiff>, RspError> {
+    let old_files = peeler.embedded_files(&manifest::load_documents(old)?)?;
+    let new_files = peeler.embedded_files(&manifest::load_documents(new)?)?;
+
+    let find = |files: &[EmbeddedFile], resource: &str, path: &[PathSegment]| {
+        files
+            .iter()
+            .find(|file| file.resource == resource && file.path == path)
+            .map(|file| file.content.clone())
+    };
+
+    let mut pairs: Vec<(String, Vec<PathSegment>, String)> = Vec::new();
+    for file in old_files.iter().chain(&new_files) {
+        if !pairs
+            .iter()
+            .any(|(resource, path, _)| *resource == file.resource && *path == file.path)
+        {
+            pairs.push((file.resource.clone(), file.path.clone(), file.key.clone()));
+        }
+    }
+
+    let mut diffs = Vec::new();
+    for (resource, path, key) in pairs {
+        let old_content = find(&old_files, &resource, &path);
+        let new_content = find(&new_files, &resource, &path);
+        if old_content == new_content {
+            continue;
+        }
+
+        let structural = match (&old_content, &new_content) {
+            (Some(old_content), Some(new_content)) => {
+                match (
+                    structured_value(peeler, &key, old_content),
+                    structured_value(peeler, &key, new_content),
+                ) {
+                    (Some(old_value), Some(new_value)) => {
+                        let mut changes = Vec::new();
+                        compare_values("", &old_value, &new_value, &mut changes);
+                        changes
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        };
+
+        diffs.push(EmbeddedDiff {
+            resource,
+            key,
+            old: old_content,
+            new: new_content,
+            structural,
+        });
+    }
+
+    Ok(diffs)
+}
+
+impl fmt::Display for EmbeddedDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = format!("{}/{}", self.resource, self.key);
+        writeln!(f, "diff {} {}", self.resource, self.key)?;
+
+        let mut options = diffy::DiffOptions::new();
+        options
+            .set_original_filename(match self.old {
+                Some(_) => format!("a/{name}"),
+                None => "/dev/null".to_string(),
+            })
+            .set_modified_filename(match self.new {
+                Some(_) => format!("b/{name}"),
+                None => "/dev/null".to_string(),
+            });
+        let patch = options.create_patch(
+            self.old.as_deref().unwrap_or_default(),
+            self.new.as_deref().unwrap_or_default(),
+        );
+        write!(f, "{patch}")?;
+
+        if !self.structural.is_empty() {
+            writeln!(f, "structural changes:")?;
+            for change in &self.structural {
+                writeln!(f, "  {change}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for StructuralChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructuralChange::Added { path, value } => write!(f, "+ {path}: {value}"),
+            StructuralChange::Removed { path, value } => write!(f, "- {path}: {value}"),
+            StructuralChange::Changed { path, old, new } => {
+                write!(f, "~ {path}: {old} -> {new}")
+            }
+        }
+    }
+}
+
+/// Parses an embedded file with the format its key names.
+fn structured_value(peeler: &Peeler, key: &str, content: &str) -> Option<JsonValue> {
+    peeler.formats().for_key(key)?.parse(content)
+}
+
+fn compare_values(
+    path: &str,
+    old: &JsonValue,
+    new: &JsonValue,
+    changes: &mut Vec<StructuralChange>,
+) {
+    match (old, new) {
+        (JsonValue::Object(old_map), JsonValue::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let child = format!("{path}{}", key_segment(key));
+                match new_map.get(key) {
+                    Some(new_value) => compare_values(&child, old_value, new_value, changes),
+                    None => changes.push(StructuralChange::Removed {
+                        path: child,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    changes.push(StructuralChange::Added {
+                        path: format!("{path}{}", key_segment(key)),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (JsonValue::Array(old_items), JsonValue::Array(new_items)) => {
+            for index in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{path}[{index}]");
+                match (old_items.get(index), new_items.get(index)) {
+                    (Some(old_value), Some(new_value)) => {
+                        compare_values(&child, old_value, new_value, changes)
+                    }
+                    (Some(old_value), None) => changes.push(StructuralChange::Removed {
+                        path: child,
+                        value: old_value.clone(),
+                    }),
+                    (None, Some(new_value)) => changes.push(StructuralChange::Added {
+                        path: child,
+                        value: new_value.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => changes.push(StructuralChange::Changed {
+            path: if path.is_empty() {
+                ".".to_string()
+            } else {
+                path.to_string()
+            },
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn key_segment(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        format!(".{key}")
+    } else {
+        format!("[{}]", JsonValue::String(key.to_string()))
+    }
+}
@@ -0,0 +1,73 @@
+use crate::error::RspError;
+use crate::format::FormatRegistry;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Opens `content` in the user's editor and returns the edited text, or
+/// `None` when it was saved unchanged.
+///
+/// The temporary file carries the key's name so editors pick the right
+/// syntax. Edits that fail validation against `formats` are kept on disk
+/// and reported in the error so they are not lost.
+pub fn edit_in_editor(
+    formats: &FormatRegistry,
+    content: &str,
+    key: &str,
+) -> Result<Option<String>, RspError> {
+    let file = tempfile::Builder::new()
+        .prefix("rsp-")
+        .suffix(&format!("-{key}"))
+        .tempfile()?;
+    fs::write(file.path(), content)?;
+
+    run_editor(file.path())?;
+
+    let edited = fs::read_to_string(file.path())?;
+    if edited == content {
+        return Ok(None);
+    }
+
+    if let Err(RspError::Validation(message)) = formats.validate(key, &edited) {
+        let (_, kept) = file
+            .keep()
+            .map_err(|e| RspError::Processing(format!("Failed to keep edited file: {e}")))?;
+        return Err(RspError::Validation(format!(
+            "{message} (your edits were saved to {})",
+            kept.display()
+        )));
+    }
+
+    Ok(Some(edited))
+}
+
+/// Checks that `content` parses as the built-in format implied by `key`'s
+/// extension. Keys without a known extension are accepted as-is.
+pub fn validate(key: &str, content: &str) -> Result<(), RspError> {
+    FormatRegistry::new().validate(key, content)
+}
+
+fn run_editor(path: &Path) -> Result<(), RspError> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| RspError::Processing("EDITOR is empty".to_string()))?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| RspError::Processing(format!("Failed to launch editor '{editor}': {e}")))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(RspError::Processing(format!(
+            "editor '{editor}' exited with {status}"
+        )))
+    }
+}
@@ -0,0 +1,166 @@
+//! Streaming YAML emitter.
+//!
+//! Writes `serde_yaml` values straight to any `io::Write` in block style,
+//! with multi-line strings as literal block scalars so embedded files stay
+//! readable. Nested mappings are indented by two spaces, and so are
+//! sequences under a mapping key.
+
+use crate::error::RspError;
+use crate::scalar;
+use serde_yaml::Value;
+use std::io::Write;
+
+pub struct Emitter<W: Write> {
+    writer: W,
+}
+
+/// What precedes a node on its first line.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// The node is the value of a `key:`; nested collections start on the
+    /// next line.
+    MappingValue,
+    /// The node follows a `- ` or `? ` indicator; nested collections start
+    /// on the same line.
+    Entry,
+}
+
+impl<W: Write> Emitter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes the `---` marker that separates two documents.
+    pub fn separator(&mut self) -> Result<(), RspError> {
+        self.writer.write_all(b"---\n")?;
+        Ok(())
+    }
+
+    /// Writes `value` as one YAML document, without a `---` marker.
+    pub fn document(&mut self, value: &Value) -> Result<(), RspError> {
+        match value {
+            Value::Mapping(map) if !map.is_empty() => self.block(value, 0, false),
+            Value::Sequence(items) if !items.is_empty() => self.block(value, 0, false),
+            Value::Tagged(tagged) => {
+                write!(self.writer, "{}", tagged.tag)?;
+                self.node(&tagged.value, 0, Context::MappingValue)
+            }
+            _ => {
+                write!(self.writer, "{}", inline(value).unwrap_or_default())?;
+                self.writer.write_all(b"\n")?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes a non-empty mapping or sequence whose entries start at
+    /// `indent` columns. With `inline_first`, the first entry continues the
+    /// current line instead of being indented.
+    fn block(&mut self, value: &Value, indent: usize, inline_first: bool) -> Result<(), RspError> {
+        let mut first = inline_first;
+        let mut start_line = |writer: &mut W| -> Result<(), RspError> {
+            if !std::mem::take(&mut first) {
+                write!(writer, "{:indent$}", "")?;
+            }
+            Ok(())
+        };
+
+        match value {
+            Value::Mapping(map) => {
+                for (key, value) in map {
+                    start_line(&mut self.writer)?;
+                    match inline_key(key) {
+                        Some(key) => {
+                            write!(self.writer, "{key}:")?;
+                        }
+                        None => {
+                            self.writer.write_all(b"?")?;
+                            self.node(key, indent, Context::Entry)?;
+                            write!(self.writer, "{:indent$}:", "")?;
+                        }
+                    }
+                    self.node(value, indent, Context::MappingValue)?;
+                }
+            }
+            Value::Sequence(items) => {
+                for item in items {
+                    start_line(&mut self.writer)?;
+                    self.writer.write_all(b"-")?;
+                    self.node(item, indent, Context::Entry)?;
+                }
+            }
+            _ => unreachable!("block() is only called with collections"),
+        }
+        Ok(())
+    }
+
+    /// Writes the node that follows a key or indicator at `indent` columns,
+    /// starting with the separating space and ending with a line break.
+    fn node(&mut self, value: &Value, indent: usize, context: Context) -> Result<(), RspError> {
+        match value {
+            Value::Mapping(map) if !map.is_empty() => match context {
+                Context::MappingValue => {
+                    self.writer.write_all(b"\n")?;
+                    self.block(value, indent + 2, false)
+                }
+                Context::Entry => {
+                    self.writer.write_all(b" ")?;
+                    self.block(value, indent + 2, true)
+                }
+            },
+            Value::Sequence(items) if !items.is_empty() => match context {
+                Context::MappingValue => {
+                    self.writer.write_all(b"\n")?;
+                    self.block(value, indent + 2, false)
+                }
+                Context::Entry => {
+                    self.writer.write_all(b" ")?;
+                    self.block(value, indent + 2, true)
+                }
+            },
+            Value::Tagged(tagged) => {
+                write!(self.writer, " {}", tagged.tag)?;
+                // A collection after a tag always starts on the next line.
+                self.node(&tagged.value, indent, Context::MappingValue)
+            }
+            Value::String(s) if s.contains('\n') && scalar::is_block_compatible(s) => {
+                writeln!(
+                    self.writer,
+                    " {}",
+                    scalar::block_scalar(s, indent, indent + 2)
+                )?;
+                Ok(())
+            }
+            _ => {
+                writeln!(self.writer, " {}", inline(value).unwrap_or_default())?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Renders scalars and empty collections on a single line.
+fn inline(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some("null".to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(scalar::flow_scalar(s)),
+        Value::Mapping(map) if map.is_empty() => Some("{}".to_string()),
+        Value::Sequence(items) if items.is_empty() => Some("[]".to_string()),
+        _ => None,
+    }
+}
+
+/// Renders a mapping key that fits before `:` on one line. Collections and
+/// tagged values need an explicit `?` entry instead.
+fn inline_key(key: &Value) -> Option<String> {
+    match key {
+        Value::Mapping(_) | Value::Sequence(_) | Value::Tagged(_) => None,
+        _ => inline(key),
+    }
+}
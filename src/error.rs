@@ -14,6 +14,18 @@ pub enum RspError {
     #[error("File not found: {0}")]
     FileNotFound(String),
 
+    #[error("Resource not found: {0}")]
+    ResourceNotFound(String),
+
+    #[error("Key not found: {0}")]
+    KeyNotFound(String),
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("Merge conflict: {0}")]
+    Conflict(String),
+
     #[error("Processing error: {0}")]
     Processing(String),
 }
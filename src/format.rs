@@ -0,0 +1,426 @@
+//! Per-format handling of embedded files.
+//!
+//! Every embedded file kind a [`Peeler`](crate::peeler::Peeler) knows about
+//! is an [`EmbeddedFormat`] held in its [`FormatRegistry`]. JSON, YAML and
+//! TOML are built in; other crates can register their own formats:
+//!
+//! ```
+//! use rsp_cli::Peeler;
+//! use rsp_cli::format::EmbeddedFormat;
+//!
+//! struct Nginx;
+//!
+//! impl EmbeddedFormat for Nginx {
+//!     fn name(&self) -> &str {
+//!         "nginx"
+//!     }
+//!
+//!     fn matches_key(&self, key: &str) -> bool {
+//!         key == "nginx.conf" || key.ends_with(".conf")
+//!     }
+//! }
+//!
+//! let mut peeler = Peeler::new();
+//! peeler.register_format(Nginx);
+//! assert!(peeler.should_process_key("nginx.conf"));
+//! ```
+
+use crate::emitter::Emitter;
+use crate::error::RspError;
+use crate::manifest;
+use serde_json::Value as JsonValue;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How one kind of embedded file is recognized, unescaped, checked and
+/// written back.
+///
+/// Only [`name`](EmbeddedFormat::name) and
+/// [`matches_key`](EmbeddedFormat::matches_key) are required. The defaults
+/// undo and redo backslash escaping, and accept any content.
+pub trait EmbeddedFormat: Send + Sync {
+    /// Short lowercase name used in reports, such as `json`.
+    fn name(&self) -> &str;
+
+    /// Whether a data key holds a file of this format, usually judged by
+    /// its extension.
+    fn matches_key(&self, key: &str) -> bool;
+
+    /// Whether `content` is recognizably of this format. Consulted only
+    /// for keys no format claims by name, under target rules that
+    /// [detect content](crate::rules::TargetRule::detect_content).
+    fn matches_content(&self, _content: &str) -> bool {
+        false
+    }
+
+    /// Turns a value as stored in the manifest into the file's content.
+    fn unescape(&self, raw: &str) -> Result<String, RspError> {
+        Ok(unescape(raw))
+    }
+
+    /// The inverse of [`unescape`](EmbeddedFormat::unescape), used when a
+    /// value that was stored escaped is written back.
+    fn escape(&self, content: &str) -> String {
+        escape(content)
+    }
+
+    /// Checks that `content` is well-formed, returning
+    /// [`RspError::Validation`] when it is not.
+    fn validate(&self, _content: &str) -> Result<(), RspError> {
+        Ok(())
+    }
+
+    /// Returns `content` in the format's canonical layout.
+    fn format(&self, content: &str) -> Result<String, RspError> {
+        Ok(content.to_string())
+    }
+
+    /// Parses `content` into a JSON value for key-level comparisons, when
+    /// the format has a data model.
+    fn parse(&self, _content: &str) -> Option<JsonValue> {
+        None
+    }
+}
+
+/// The formats a [`Peeler`](crate::peeler::Peeler) recognizes. Formats
+/// registered later take precedence over earlier ones and the built-ins.
+#[derive(Clone)]
+pub struct FormatRegistry {
+    formats: Vec<Arc<dyn EmbeddedFormat>>,
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FormatRegistry {
+    /// A registry with the built-in JSON, YAML and TOML formats, plain
+    /// text for strings selected without a recognizable format, and Grafana
+    /// dashboards for the `grafana` profile.
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Text);
+        registry.register(Json);
+        registry.register(Yaml);
+        registry.register(Toml);
+        registry.register(GrafanaDashboard::new());
+        registry
+    }
+
+    /// A registry that recognizes nothing.
+    pub fn empty() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, format: impl EmbeddedFormat + 'static) {
+        self.formats.push(Arc::new(format));
+    }
+
+    /// Looks a format up by name.
+    pub fn get(&self, name: &str) -> Option<&dyn EmbeddedFormat> {
+        self.iter().find(|format| format.name() == name)
+    }
+
+    /// The format claiming `key` by name.
+    pub fn for_key(&self, key: &str) -> Option<&dyn EmbeddedFormat> {
+        self.iter().find(|format| format.matches_key(key))
+    }
+
+    /// The format of the file stored under `key`, judged by name first and
+    /// by content otherwise.
+    pub fn detect(&self, key: &str, content: &str) -> Option<&dyn EmbeddedFormat> {
+        self.for_key(key)
+            .or_else(|| self.iter().find(|format| format.matches_content(content)))
+    }
+
+    /// Checks `content` with the format of the file stored under `key`.
+    /// Files of no known format are accepted as-is.
+    pub fn validate(&self, key: &str, content: &str) -> Result<(), RspError> {
+        match self.for_key(key) {
+            Some(format) => validate_as(format, key, content),
+            None => Ok(()),
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &dyn EmbeddedFormat> {
+        self.formats.iter().rev().map(|format| format.as_ref())
+    }
+}
+
+/// Validates `content` as `format`, naming the format and `key` in the
+/// error.
+pub(crate) fn validate_as(
+    format: &dyn EmbeddedFormat,
+    key: &str,
+    content: &str,
+) -> Result<(), RspError> {
+    format.validate(content).map_err(|e| match e {
+        RspError::Validation(message) => RspError::Validation(format!(
+            "invalid {} in {key}: {message}",
+            format.name().to_uppercase()
+        )),
+        other => other,
+    })
+}
+
+/// Replaces the backslash escapes `\n`, `\t`, `\r`, `\\` and `\"` with the
+/// characters they stand for. Other backslashes are kept.
+pub fn unescape(escaped: &str) -> String {
+    let mut result = String::new();
+    let mut chars = escaped.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some('\\') => result.push('\\'),
+                Some('"') => result.push('"'),
+                Some(c) => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+/// Escapes a string so that [`unescape`] restores it.
+pub fn escape(unescaped: &str) -> String {
+    let mut result = String::with_capacity(unescaped.len());
+    for ch in unescaped.chars() {
+        match ch {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn has_extension(key: &str, extensions: &[&str]) -> bool {
+    Path::new(key)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extensions.contains(&extension))
+}
+
+/// Any text. Claims no key, so it is only used when asked for by name.
+pub struct Text;
+
+impl EmbeddedFormat for Text {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn matches_key(&self, _key: &str) -> bool {
+        false
+    }
+
+    /// Only unescapes one-liners: text that already spans several lines,
+    /// such as a script, keeps backslashes like the one in `printf '%s\n'`.
+    fn unescape(&self, raw: &str) -> Result<String, RspError> {
+        if raw.contains('\n') {
+            Ok(raw.to_string())
+        } else {
+            Ok(unescape(raw))
+        }
+    }
+}
+
+pub struct Json;
+
+impl EmbeddedFormat for Json {
+    fn name(&self) -> &str {
+        "json"
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        has_extension(key, &["json"])
+    }
+
+    /// A JSON object or array as stored.
+    fn matches_content(&self, content: &str) -> bool {
+        content.trim_start().starts_with(['{', '['])
+            && serde_json::from_str::<JsonValue>(content).is_ok()
+    }
+
+    fn validate(&self, content: &str) -> Result<(), RspError> {
+        serde_json::from_str::<JsonValue>(content)
+            .map(|_| ())
+            .map_err(|e| RspError::Validation(e.to_string()))
+    }
+
+    /// Pretty-prints with two-space indentation and sorted keys.
+    fn format(&self, content: &str) -> Result<String, RspError> {
+        let value: JsonValue =
+            serde_json::from_str(content).map_err(|e| RspError::Validation(e.to_string()))?;
+        let mut formatted = serde_json::to_string_pretty(&value)
+            .map_err(|e| RspError::Processing(e.to_string()))?;
+        formatted.push('\n');
+        Ok(formatted)
+    }
+
+    fn parse(&self, content: &str) -> Option<JsonValue> {
+        serde_json::from_str(content).ok()
+    }
+}
+
+pub struct Yaml;
+
+impl EmbeddedFormat for Yaml {
+    fn name(&self) -> &str {
+        "yaml"
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        has_extension(key, &["yaml", "yml"])
+    }
+
+    fn validate(&self, content: &str) -> Result<(), RspError> {
+        manifest::load_documents(content)
+            .map(|_| ())
+            .map_err(|e| RspError::Validation(e.to_string()))
+    }
+
+    /// Re-emits every document in block style with two-space indentation.
+    /// Comments and anchors are not kept.
+    fn format(&self, content: &str) -> Result<String, RspError> {
+        let documents =
+            manifest::load_documents(content).map_err(|e| RspError::Validation(e.to_string()))?;
+        let mut emitter = Emitter::new(Vec::new());
+        for (index, document) in documents.iter().enumerate() {
+            if index > 0 {
+                emitter.separator()?;
+            }
+            emitter.document(document)?;
+        }
+        String::from_utf8(emitter.into_inner())
+            .map_err(|e| RspError::Processing(format!("emitter wrote invalid UTF-8: {e}")))
+    }
+
+    fn parse(&self, content: &str) -> Option<JsonValue> {
+        serde_yaml::from_str(content).ok()
+    }
+}
+
+pub struct Toml;
+
+impl EmbeddedFormat for Toml {
+    fn name(&self) -> &str {
+        "toml"
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        has_extension(key, &["toml"])
+    }
+
+    fn validate(&self, content: &str) -> Result<(), RspError> {
+        toml::from_str::<toml::Table>(content)
+            .map(|_| ())
+            .map_err(|e| RspError::Validation(e.to_string()))
+    }
+
+    fn parse(&self, content: &str) -> Option<JsonValue> {
+        toml::from_str(content).ok()
+    }
+}
+
+/// Grafana dashboard JSON. Claims no key, so it is only used when asked
+/// for by name.
+pub struct GrafanaDashboard {
+    strip_volatile: bool,
+}
+
+impl GrafanaDashboard {
+    /// Fields Grafana rewrites on every save.
+    pub const VOLATILE_FIELDS: &'static [&'static str] = &["id", "version", "iteration"];
+
+    pub fn new() -> Self {
+        Self {
+            strip_volatile: false,
+        }
+    }
+
+    /// Removes [`VOLATILE_FIELDS`](GrafanaDashboard::VOLATILE_FIELDS)
+    /// from the dashboard when formatting it.
+    pub fn strip_volatile(mut self, enabled: bool) -> Self {
+        self.strip_volatile = enabled;
+        self
+    }
+}
+
+impl Default for GrafanaDashboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmbeddedFormat for GrafanaDashboard {
+    fn name(&self) -> &str {
+        "grafana"
+    }
+
+    fn matches_key(&self, _key: &str) -> bool {
+        false
+    }
+
+    /// Checks the content is a JSON object with a `title` and, if present,
+    /// a `panels` list.
+    fn validate(&self, content: &str) -> Result<(), RspError> {
+        let value: JsonValue =
+            serde_json::from_str(content).map_err(|e| RspError::Validation(e.to_string()))?;
+        let Some(dashboard) = value.as_object() else {
+            return Err(RspError::Validation(
+                "a dashboard must be a JSON object".to_string(),
+            ));
+        };
+        if !dashboard.get("title").is_some_and(JsonValue::is_string) {
+            return Err(RspError::Validation(
+                "the dashboard has no title".to_string(),
+            ));
+        }
+        if dashboard
+            .get("panels")
+            .is_some_and(|panels| !panels.is_array())
+        {
+            return Err(RspError::Validation("panels must be a list".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Pretty-prints with sorted keys, without the volatile fields when
+    /// asked to.
+    fn format(&self, content: &str) -> Result<String, RspError> {
+        let mut value: JsonValue =
+            serde_json::from_str(content).map_err(|e| RspError::Validation(e.to_string()))?;
+        if self.strip_volatile
+            && let Some(dashboard) = value.as_object_mut()
+        {
+            for field in Self::VOLATILE_FIELDS {
+                dashboard.remove(*field);
+            }
+        }
+        let mut formatted = serde_json::to_string_pretty(&value)
+            .map_err(|e| RspError::Processing(e.to_string()))?;
+        formatted.push('\n');
+        Ok(formatted)
+    }
+
+    fn parse(&self, content: &str) -> Option<JsonValue> {
+        serde_json::from_str(content).ok()
+    }
+}
@@ -0,0 +1,119 @@
+use crate::error::RspError;
+use crate::manifest;
+use crate::peeler::Peeler;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Reads a file as of a git revision, given as `REV:PATH`.
+pub fn show(spec: &str) -> Result<String, RspError> {
+    let output = Command::new("git")
+        .args(["show", spec])
+        .output()
+        .map_err(|e| RspError::Processing(format!("Failed to run git: {e}")))?;
+
+    if !output.status.success() {
+        return Err(RspError::FileNotFound(format!(
+            "{spec} ({})",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    String::from_utf8(output.stdout)
+        .map_err(|_| RspError::InvalidFormat(format!("{spec} is not valid UTF-8")))
+}
+
+/// Converts a file for `diff.<driver>.textconv`. This never fails: input
+/// with no values claimed by the target rules, or that cannot be peeled, is
+/// returned as-is.
+pub fn textconv(peeler: &Peeler, input: &[u8]) -> Vec<u8> {
+    let Ok(content) = std::str::from_utf8(input) else {
+        return input.to_vec();
+    };
+    let has_targets = manifest::load_documents(content).is_ok_and(|documents| {
+        peeler
+            .embedded_files(&documents)
+            .is_ok_and(|files| !files.is_empty())
+    });
+    if !has_targets {
+        return input.to_vec();
+    }
+
+    match peeler.peel_str(content) {
+        Ok(output) => output.text.into_bytes(),
+        Err(_) => input.to_vec(),
+    }
+}
+
+/// Configures the current repository to show peeled manifests in `git diff`
+/// and `git log -p` and to merge them with `rsp merge-driver`, returning a
+/// description of each change made.
+///
+/// Attribute lines and config entries that already exist are left alone, so
+/// running this again is harmless.
+pub fn install(driver: &str, patterns: &[String]) -> Result<Vec<String>, RspError> {
+    let root = git_output(&["rev-parse", "--show-toplevel"])?;
+    let attributes_path = Path::new(root.trim()).join(".gitattributes");
+    let mut attributes = match fs::read_to_string(&attributes_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut actions = Vec::new();
+    for pattern in patterns {
+        let line = format!("{pattern} diff={driver} merge={driver}");
+        if attributes.lines().any(|existing| existing.trim() == line) {
+            continue;
+        }
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(&line);
+        attributes.push('\n');
+        actions.push(format!("Added '{line}' to {}", attributes_path.display()));
+    }
+    if !actions.is_empty() {
+        fs::write(&attributes_path, attributes)?;
+    }
+
+    for (key, value) in [
+        (format!("diff.{driver}.textconv"), "rsp textconv"),
+        (format!("diff.{driver}.cachetextconv"), "true"),
+        (
+            format!("merge.{driver}.name"),
+            "rsp merge driver for manifests with embedded files",
+        ),
+        (
+            format!("merge.{driver}.driver"),
+            "rsp merge-driver %O %A %B --marker-size %L",
+        ),
+    ] {
+        let current = git_output(&["config", "--local", "--get", &key]).ok();
+        if current.as_deref().map(str::trim) == Some(value) {
+            continue;
+        }
+        git_output(&["config", "--local", &key, value])?;
+        actions.push(format!("Set git config {key} = {value}"));
+    }
+
+    Ok(actions)
+}
+
+fn git_output(args: &[&str]) -> Result<String, RspError> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| RspError::Processing(format!("Failed to run git: {e}")))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(RspError::Processing(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
@@ -0,0 +1,258 @@
+//! Running as a KRM function, for `kustomize` transformers and other tools
+//! following the [KRM functions specification].
+//!
+//! The function reads a `ResourceList`, peels (or packs) the embedded files
+//! of every resource in its `items`, and writes the list back with a
+//! `results` entry for each warning and error. Settings come from the
+//! `spec` of the `functionConfig`, which takes the same fields as
+//! `.rsp.toml` plus `mode`, `keys` and `unescape`:
+//!
+//! ```yaml
+//! functionConfig:
+//!   apiVersion: rsp.dev/v1alpha1
+//!   kind: PeelConfig
+//!   metadata:
+//!     name: peel
+//!   spec:
+//!     mode: peel
+//!     normalize: true
+//!     profiles:
+//!       grafana: true
+//! ```
+//!
+//! A `ConfigMap` works too; each value in its `data` is read as YAML, so
+//! `normalize: "true"` turns normalizing on.
+//!
+//! [KRM functions specification]: https://github.com/kubernetes-sigs/kustomize/blob/master/cmd/config/docs/api-conventions/functions-spec.md
+
+use crate::config::Config;
+use crate::emitter::Emitter;
+use crate::error::RspError;
+use crate::manifest;
+use crate::options::PeelOptions;
+use crate::peeler::Peeler;
+use crate::report::{EntryStatus, PeelReport};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+/// What the function does with the embedded files it finds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Unescape them, like `rsp peel`.
+    #[default]
+    Peel,
+    /// Escape them back into single-line strings.
+    Pack,
+}
+
+#[derive(Debug, Default)]
+struct FunctionSpec {
+    mode: Mode,
+    keys: Vec<String>,
+    unescape: Option<bool>,
+    config: Config,
+}
+
+impl FunctionSpec {
+    /// Takes the function's own fields out of `spec` and reads the rest as
+    /// `.rsp.toml` settings, so unknown fields are still rejected.
+    fn from_mapping(mut spec: Mapping) -> Result<Self, serde_yaml::Error> {
+        Ok(Self {
+            mode: take(&mut spec, "mode")?.unwrap_or_default(),
+            keys: take(&mut spec, "keys")?.unwrap_or_default(),
+            unescape: take(&mut spec, "unescape")?,
+            config: serde_yaml::from_value(Value::Mapping(spec))?,
+        })
+    }
+}
+
+fn take<T: DeserializeOwned>(
+    spec: &mut Mapping,
+    field: &str,
+) -> Result<Option<T>, serde_yaml::Error> {
+    spec.remove(field).map(serde_yaml::from_value).transpose()
+}
+
+/// One entry of the `results` list.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionResult {
+    message: String,
+    severity: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resource_ref: Option<ResourceRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<Field>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceRef {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Field {
+    path: String,
+}
+
+/// The `ResourceList` written back, and whether any error was reported in
+/// its `results`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionOutput {
+    pub text: String,
+    pub errors: bool,
+}
+
+/// Runs the function on the `ResourceList` in `input`, with the settings
+/// of its `functionConfig` applied on top of `options`.
+///
+/// Only input that is not a `ResourceList` is an error. Problems with the
+/// `functionConfig` or single items are reported in `results`, leaving the
+/// items concerned unchanged.
+pub fn run(options: PeelOptions, input: &str) -> Result<FunctionOutput, RspError> {
+    let mut list: Value = serde_yaml::from_str(input)?;
+    if manifest::kind(&list) != Some("ResourceList") {
+        return Err(RspError::InvalidFormat(
+            "expected a ResourceList on stdin".to_string(),
+        ));
+    }
+
+    let mut results = Vec::new();
+    match function_spec(list.get("functionConfig")) {
+        Ok(spec) => {
+            let mut options = spec.config.apply(options);
+            if let Some(unescape) = spec.unescape {
+                options = options.unescape(unescape);
+            }
+            for key in &spec.keys {
+                options = options.key(key);
+            }
+            let peeler = Peeler::with_options(options);
+
+            if let Some(Value::Sequence(items)) = list.get_mut("items") {
+                for item in items {
+                    process_item(&peeler, spec.mode, item, &mut results);
+                }
+            }
+        }
+        Err(e) => results.push(FunctionResult {
+            message: e.to_string(),
+            severity: "error",
+            resource_ref: None,
+            field: None,
+        }),
+    }
+
+    let errors = results.iter().any(|result| result.severity == "error");
+    if !results.is_empty() {
+        let Value::Mapping(list) = &mut list else {
+            unreachable!("a ResourceList has a kind, so it is a mapping");
+        };
+        let existing = list
+            .entry(Value::from("results"))
+            .or_insert_with(|| Value::Sequence(Vec::new()));
+        if let Value::Sequence(existing) = existing {
+            for result in &results {
+                existing.push(serde_yaml::to_value(result)?);
+            }
+        }
+    }
+
+    let mut emitter = Emitter::new(Vec::new());
+    emitter.document(&list)?;
+    let text = String::from_utf8(emitter.into_inner())
+        .map_err(|e| RspError::Processing(format!("emitter wrote invalid UTF-8: {e}")))?;
+    Ok(FunctionOutput { text, errors })
+}
+
+/// Reads the settings from the `spec` of `function_config`, or from the
+/// `data` of a ConfigMap.
+fn function_spec(function_config: Option<&Value>) -> Result<FunctionSpec, RspError> {
+    let invalid =
+        |e: serde_yaml::Error| RspError::InvalidFormat(format!("invalid functionConfig: {e}"));
+
+    let Some(function_config) = function_config.filter(|config| !config.is_null()) else {
+        return Ok(FunctionSpec::default());
+    };
+    let spec = match (function_config.get("spec"), function_config.get("data")) {
+        (Some(Value::Mapping(spec)), _) => spec.clone(),
+        (Some(_), _) => {
+            return Err(RspError::InvalidFormat(
+                "invalid functionConfig: spec must be a mapping".to_string(),
+            ));
+        }
+        (None, Some(Value::Mapping(data))) if manifest::is_configmap(function_config) => {
+            let mut spec = Mapping::new();
+            for (key, value) in data {
+                let value = match value {
+                    Value::String(text) => serde_yaml::from_str(text).map_err(invalid)?,
+                    other => other.clone(),
+                };
+                spec.insert(key.clone(), value);
+            }
+            spec
+        }
+        _ => return Ok(FunctionSpec::default()),
+    };
+    FunctionSpec::from_mapping(spec).map_err(invalid)
+}
+
+fn process_item(peeler: &Peeler, mode: Mode, item: &mut Value, results: &mut Vec<FunctionResult>) {
+    let resource_ref = ResourceRef {
+        api_version: item
+            .get("apiVersion")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        kind: manifest::kind(item).map(str::to_string),
+        name: manifest::name(item).map(str::to_string),
+        namespace: manifest::namespace(item).map(str::to_string),
+    };
+
+    let mut processed = item.clone();
+    let report = match mode {
+        Mode::Peel => peeler.peel_value(&mut processed),
+        Mode::Pack => peeler
+            .pack_value(&mut processed)
+            .map(|()| PeelReport::default()),
+    };
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            results.push(FunctionResult {
+                message: e.to_string(),
+                severity: "error",
+                resource_ref: Some(resource_ref),
+                field: None,
+            });
+            return;
+        }
+    };
+    *item = processed;
+
+    for entry in &report.entries {
+        let result = |message: &str, severity| FunctionResult {
+            message: message.to_string(),
+            severity,
+            resource_ref: Some(resource_ref.clone()),
+            field: Some(Field {
+                path: entry.path.clone(),
+            }),
+        };
+        if let EntryStatus::Failed { error } = &entry.status {
+            results.push(result(error, "error"));
+        }
+        for warning in &entry.warnings {
+            results.push(result(warning, "warning"));
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! Kustomize `configMapGenerator` and `secretGenerator` literals.
+//!
+//! A literal is a `KEY=VALUE` string, so an embedded file ends up escaped
+//! inside it just like in a ConfigMap's `data`. The `kustomize` profile
+//! peels the `VALUE` part in place; [`extract_literals`] goes further and
+//! moves long literals into real files listed under the generator's
+//! `files`, which generates the same ConfigMap or Secret.
+
+use crate::builder;
+use crate::error::RspError;
+use crate::scalar;
+use crate::selector::{PathSegment, display_path};
+use crate::splice;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The names kustomize looks for in a directory.
+pub const FILE_NAMES: &[&str] = &["kustomization.yaml", "kustomization.yml", "Kustomization"];
+
+/// The generator fields holding literals.
+pub const GENERATORS: &[&str] = &["configMapGenerator", "secretGenerator"];
+
+/// One `KEY=VALUE` literal, parsed the way kustomize does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Literal {
+    pub key: String,
+    pub value: String,
+    /// The quote kustomize strips from around the value, if any.
+    quote: Option<char>,
+}
+
+impl Literal {
+    /// Splits `source` at its first `=`. Returns `None` when there is no
+    /// `=` or the key is empty.
+    pub fn parse(source: &str) -> Option<Self> {
+        let (key, value) = source.split_once('=')?;
+        if key.is_empty() {
+            return None;
+        }
+        let quote = value
+            .chars()
+            .next()
+            .filter(|quote| matches!(quote, '"' | '\'') && value.len() >= 2)
+            .filter(|quote| value.ends_with(*quote));
+        let value = match quote {
+            Some(_) => &value[1..value.len() - 1],
+            None => value,
+        };
+        Some(Self {
+            key: key.to_string(),
+            value: value.to_string(),
+            quote,
+        })
+    }
+
+    /// The literal with its value replaced by `value`, quoted as before.
+    pub fn with_value(&self, value: &str) -> String {
+        match self.quote {
+            Some(quote) => format!("{}={quote}{value}{quote}", self.key),
+            None => format!("{}={value}", self.key),
+        }
+    }
+}
+
+/// A literal moved into a file by [`extract_literals`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedLiteral {
+    /// The `name` of the generator the literal belonged to.
+    pub generator: String,
+    pub key: String,
+    /// Where the file goes, relative to the kustomization's directory:
+    /// `<generator>/<key>`.
+    pub path: String,
+    /// The file content, the literal's value as kustomize reads it.
+    pub content: String,
+}
+
+/// Moves the literals of `kustomization` whose value spans several lines
+/// or is at least `min_length` bytes long into `files` entries, and
+/// returns the files to write.
+///
+/// The generated ConfigMaps and Secrets are unchanged once the files are
+/// written: each file holds the literal's value byte for byte, and its
+/// name is the literal's key.
+pub fn extract_literals(
+    kustomization: &mut Value,
+    min_length: usize,
+) -> Result<Vec<ExtractedLiteral>, RspError> {
+    let mut extracted: Vec<ExtractedLiteral> = Vec::new();
+
+    for field in GENERATORS {
+        let Some(Value::Sequence(generators)) = kustomization.get_mut(*field) else {
+            continue;
+        };
+        for generator in generators {
+            let Value::Mapping(generator) = generator else {
+                continue;
+            };
+            let Some(name) = generator.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            let name = name.to_string();
+            if generator
+                .get("files")
+                .is_some_and(|files| !files.is_sequence())
+            {
+                continue;
+            }
+            let Some(Value::Sequence(literals)) = generator.get_mut("literals") else {
+                continue;
+            };
+
+            let mut files = Vec::new();
+            let mut error = None;
+            literals.retain(|literal| {
+                let Some(literal) = literal.as_str().and_then(Literal::parse) else {
+                    return true;
+                };
+                // The key becomes a file name, so it must be a valid one.
+                if builder::validate_key(&literal.key).is_err() {
+                    return true;
+                }
+                if !literal.value.contains('\n') && literal.value.len() < min_length {
+                    return true;
+                }
+                let file = ExtractedLiteral {
+                    path: format!("{name}/{}", literal.key),
+                    generator: name.clone(),
+                    key: literal.key,
+                    content: literal.value,
+                };
+                if extracted
+                    .iter()
+                    .any(|other| other.path == file.path && other.content != file.content)
+                {
+                    error.get_or_insert_with(|| {
+                        RspError::Conflict(format!(
+                            "two generators named '{name}' would both write {}",
+                            file.path
+                        ))
+                    });
+                    return true;
+                }
+                files.push(Value::String(file.path.clone()));
+                extracted.push(file);
+                false
+            });
+            if let Some(error) = error {
+                return Err(error);
+            }
+
+            if literals.is_empty() {
+                generator.shift_remove("literals");
+            }
+            if !files.is_empty() {
+                append_files(generator, files);
+            }
+        }
+    }
+    Ok(extracted)
+}
+
+/// [`extract_literals`] for the text of a kustomization: the moved
+/// literals are removed and their files listed in place, so comments and
+/// the layout of everything else are kept. Returns the new text and the
+/// files to write.
+pub fn extract_literals_in_place(
+    content: &str,
+    min_length: usize,
+) -> Result<(String, Vec<ExtractedLiteral>), RspError> {
+    let original: Value = serde_yaml::from_str(content)?;
+    let mut updated = original.clone();
+    let extracted = extract_literals(&mut updated, min_length)?;
+    if extracted.is_empty() {
+        return Ok((content.to_string(), extracted));
+    }
+
+    let locate = |path: &[PathSegment]| {
+        splice::locate_entry(content, 0, path).ok_or_else(|| {
+            RspError::Processing(format!(
+                "could not locate '{}' in the kustomization",
+                display_path(path)
+            ))
+        })
+    };
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    for field in GENERATORS {
+        let (Some(Value::Sequence(before)), Some(Value::Sequence(after))) =
+            (original.get(*field), updated.get(*field))
+        else {
+            continue;
+        };
+        for (index, (before, after)) in before.iter().zip(after).enumerate() {
+            let sequence = |value: &Value, key: &str| {
+                value
+                    .get(key)
+                    .and_then(Value::as_sequence)
+                    .cloned()
+                    .unwrap_or_default()
+            };
+            let (literals, kept) = (sequence(before, "literals"), sequence(after, "literals"));
+            if literals == kept {
+                continue;
+            }
+            let existing_files = sequence(before, "files");
+            let all_files = sequence(after, "files");
+            let new_files = &all_files[existing_files.len()..];
+
+            let generator = [
+                PathSegment::Key(field.to_string()),
+                PathSegment::Index(index),
+            ];
+            let path = |segments: &[PathSegment]| [generator.as_slice(), segments].concat();
+            let literals_path = path(&[PathSegment::Key("literals".to_string())]);
+            let literals_entry = locate(&literals_path)?;
+            let file_lines = |indent: usize| {
+                new_files
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(|file| {
+                        let file = if scalar::is_plain_safe(file) {
+                            file.to_string()
+                        } else {
+                            scalar::double_quoted(file)
+                        };
+                        format!("{}- {file}\n", " ".repeat(indent))
+                    })
+                    .collect::<String>()
+            };
+            let files_field = || {
+                let indent = literals_entry.indent;
+                format!("{}files:\n{}", " ".repeat(indent), file_lines(indent + 2))
+            };
+
+            if kept.is_empty() {
+                let replacement = if existing_files.is_empty() {
+                    files_field()
+                } else {
+                    String::new()
+                };
+                edits.push((literals_entry.start, literals_entry.end, replacement));
+            } else {
+                let mut position = 0;
+                for (literal_index, literal) in literals.iter().enumerate() {
+                    if kept.get(position) == Some(literal) {
+                        position += 1;
+                        continue;
+                    }
+                    let literal_path = [
+                        literals_path.as_slice(),
+                        &[PathSegment::Index(literal_index)],
+                    ]
+                    .concat();
+                    let entry = locate(&literal_path)?;
+                    edits.push((entry.start, entry.end, String::new()));
+                }
+                if existing_files.is_empty() {
+                    edits.push((literals_entry.end, literals_entry.end, files_field()));
+                }
+            }
+            if !existing_files.is_empty() && !new_files.is_empty() {
+                let files_path = path(&[PathSegment::Key("files".to_string())]);
+                let files_entry = locate(&files_path)?;
+                let first_file =
+                    locate(&[files_path.as_slice(), &[PathSegment::Index(0)]].concat())?;
+                edits.push((
+                    files_entry.end,
+                    files_entry.end,
+                    file_lines(first_file.indent),
+                ));
+            }
+        }
+    }
+
+    edits.sort_by_key(|(start, end, _)| std::cmp::Reverse((*start, *end)));
+    let mut text = content.to_string();
+    for (start, end, replacement) in edits {
+        let replacement = if start == text.len() && !text.is_empty() && !text.ends_with('\n') {
+            format!("\n{replacement}")
+        } else {
+            replacement
+        };
+        text.replace_range(start..end, &replacement);
+    }
+
+    if serde_yaml::from_str::<Value>(&text).ok().as_ref() != Some(&updated) {
+        return Err(RspError::Processing(
+            "could not update the kustomization in place".to_string(),
+        ));
+    }
+    Ok((text, extracted))
+}
+
+fn append_files(generator: &mut Mapping, files: Vec<Value>) {
+    let existing = generator
+        .entry(Value::from("files"))
+        .or_insert_with(|| Value::Sequence(Vec::new()));
+    if let Value::Sequence(existing) = existing {
+        for file in files {
+            if !existing.contains(&file) {
+                existing.push(file);
+            }
+        }
+    }
+}
+
+/// The kustomization file `path` stands for: `path` itself, or the
+/// kustomization file in it when it is a directory.
+pub fn kustomization_file(path: &Path) -> Result<PathBuf, RspError> {
+    if !path.is_dir() {
+        return Ok(path.to_path_buf());
+    }
+    FILE_NAMES
+        .iter()
+        .map(|name| path.join(name))
+        .find(|file| file.is_file())
+        .ok_or_else(|| RspError::FileNotFound(path.join(FILE_NAMES[0]).display().to_string()))
+}
+
+/// Writes the files of `extracted` under `dir`, the kustomization's
+/// directory. Nothing is written if any of them already exists with
+/// other content.
+pub fn write_files(dir: &Path, extracted: &[ExtractedLiteral]) -> Result<(), RspError> {
+    for file in extracted {
+        let path = dir.join(&file.path);
+        if path.exists() && fs::read(&path)? != file.content.as_bytes() {
+            return Err(RspError::Conflict(format!(
+                "{} already exists with other content",
+                path.display()
+            )));
+        }
+    }
+    for file in extracted {
+        let path = dir.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.content)?;
+    }
+    Ok(())
+}
@@ -1,7 +1,35 @@
+pub mod builder;
 pub mod cli;
+pub mod config;
+pub mod diff;
+pub mod edit;
+pub mod emitter;
 pub mod error;
+pub mod format;
+pub mod git;
+pub mod krm;
+pub mod kustomize;
+pub mod manifest;
+pub mod merge;
+pub mod options;
 pub mod peeler;
+pub mod plugin;
+pub mod profiles;
+pub mod report;
+pub mod rules;
+mod scalar;
+pub mod selector;
+mod splice;
+mod template;
+mod toml_document;
 
+pub use builder::ConfigMapBuilder;
 pub use cli::Cli;
 pub use error::RspError;
+pub use format::{EmbeddedFormat, FormatRegistry};
+pub use options::{AnnotationMode, DocumentFormat, PeelOptions};
 pub use peeler::Peeler;
+pub use plugin::KubectlPlugin;
+pub use profiles::Profile;
+pub use report::{EntryStatus, PeelOutput, PeelReport, ReportEntry};
+pub use rules::TargetRule;
@@ -1,9 +1,4 @@
-mod cli;
-mod error;
-mod peeler;
-
-use cli::Cli;
-use error::RspError;
+use rsp_cli::{Cli, RspError};
 
 fn main() -> Result<(), RspError> {
     let cli = Cli::new();
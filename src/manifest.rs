@@ -0,0 +1,120 @@
+use crate::error::RspError;
+use serde::Deserialize;
+use serde_yaml::Value;
+
+/// Parses every document of a (possibly multi-document) YAML stream,
+/// skipping empty documents such as the one after a trailing `---`.
+pub fn load_documents(content: &str) -> Result<Vec<Value>, RspError> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(content) {
+        let value = Value::deserialize(document)?;
+        if !value.is_null() {
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// Parses a stream of JSON values, such as one `kubectl get -o json`
+/// object or several concatenated ones.
+pub fn load_json_documents(content: &str) -> Result<Vec<Value>, RspError> {
+    serde_json::Deserializer::from_str(content)
+        .into_iter::<Value>()
+        .map(|document| document.map_err(|e| RspError::InvalidFormat(format!("invalid JSON: {e}"))))
+        .collect()
+}
+
+pub fn kind(value: &Value) -> Option<&str> {
+    value.get("kind").and_then(Value::as_str)
+}
+
+pub fn name(value: &Value) -> Option<&str> {
+    value
+        .get("metadata")
+        .and_then(|metadata| metadata.get("name"))
+        .and_then(Value::as_str)
+}
+
+pub fn namespace(value: &Value) -> Option<&str> {
+    value
+        .get("metadata")
+        .and_then(|metadata| metadata.get("namespace"))
+        .and_then(Value::as_str)
+}
+
+/// Identifies a resource as `Kind/namespace/name`, leaving out the parts
+/// the document does not set.
+pub fn resource_id(value: &Value) -> String {
+    [kind(value), namespace(value), name(value)]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Removes the given annotations, and the `annotations` mapping itself
+/// when nothing is left in it.
+pub fn strip_annotations<'a>(value: &mut Value, keys: impl IntoIterator<Item = &'a str>) {
+    let Some(Value::Mapping(metadata)) = value.get_mut("metadata") else {
+        return;
+    };
+    if let Some(Value::Mapping(annotations)) = metadata.get_mut("annotations") {
+        for key in keys {
+            annotations.shift_remove(key);
+        }
+        if annotations.is_empty() {
+            metadata.shift_remove("annotations");
+        }
+    }
+}
+
+/// Whether `value` is a `List` of resources, as printed by `kubectl get`
+/// for several objects.
+pub fn is_list(value: &Value) -> bool {
+    kind(value) == Some("List")
+}
+
+pub fn is_configmap(value: &Value) -> bool {
+    kind(value) == Some("ConfigMap")
+}
+
+/// Finds the raw `data` value stored under `key`, optionally restricted to
+/// the ConfigMap whose `metadata.name` is `name`. Returns the index of the
+/// document holding it along with the value.
+pub fn find_data_value<'a>(
+    documents: &'a [Value],
+    key: &str,
+    name_filter: Option<&str>,
+) -> Result<(usize, &'a str), RspError> {
+    let configmaps: Vec<(usize, &Value)> = documents
+        .iter()
+        .enumerate()
+        .filter(|(_, document)| is_configmap(document))
+        .filter(|(_, document)| name_filter.is_none() || name(document) == name_filter)
+        .collect();
+
+    if configmaps.is_empty() {
+        return Err(RspError::ResourceNotFound(match name_filter {
+            Some(name) => format!("ConfigMap '{name}'"),
+            None => "ConfigMap".to_string(),
+        }));
+    }
+
+    let mut matches = configmaps.iter().filter_map(|(index, configmap)| {
+        configmap
+            .get("data")
+            .and_then(|data| data.get(key))
+            .map(|value| (*index, value))
+    });
+
+    match (matches.next(), matches.next()) {
+        (Some((index, value)), None) => value
+            .as_str()
+            .map(|value| (index, value))
+            .ok_or_else(|| RspError::InvalidFormat(format!("data key '{key}' is not a string"))),
+        (Some(_), Some(_)) => Err(RspError::Processing(format!(
+            "key '{key}' exists in several ConfigMaps, select one with --name"
+        ))),
+        (None, _) => Err(RspError::KeyNotFound(key.to_string())),
+    }
+}
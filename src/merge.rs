@@ -0,0 +1,254 @@
+//! Three-way merging of manifests for use as a git merge driver.
+//!
+//! Every embedded file is cut out of the three versions and replaced by a
+//! placeholder that is identical on all sides, so the surrounding manifest
+//! merges line by line like any other YAML file. Each embedded file is then
+//! merged on its own, line by line in peeled form, and packed back into the
+//! placeholder in the style our side used for it.
+
+use crate::error::RspError;
+use crate::manifest;
+use crate::peeler::{EmbeddedFile, Peeler};
+use crate::selector::{self, PathSegment};
+use crate::splice::{self, ScalarSpan, ScalarStyle};
+use serde_yaml::Value;
+
+pub struct MergeOutcome {
+    pub text: String,
+    pub conflicts: bool,
+}
+
+struct Version {
+    masked: String,
+    files: Vec<EmbeddedFile>,
+    /// Where each file's value is, for those that could be located.
+    spans: Vec<Option<ScalarSpan>>,
+    /// Whether each file's raw value was escaped twice.
+    double_escaped: Vec<bool>,
+}
+
+/// Merges `ours` and `theirs` against their common ancestor `base`.
+///
+/// Manifests that cannot be parsed fall back to a plain line-level merge,
+/// and so do embedded files one side deleted while the other changed them,
+/// which always counts as a conflict. Embedded files that cannot be
+/// located in the text are merged with the lines around them.
+pub fn merge_manifests(
+    peeler: &Peeler,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    marker_length: usize,
+) -> MergeOutcome {
+    let mut options = diffy::MergeOptions::new();
+    options.set_conflict_marker_length(marker_length);
+
+    let mut ids: Vec<(String, Vec<PathSegment>)> = Vec::new();
+    let versions = [base, ours, theirs].map(|text| mask(peeler, text, &mut ids).ok());
+    let [Some(base_version), Some(our_version), Some(their_version)] = versions else {
+        return line_merge(&options, base, ours, theirs);
+    };
+
+    // A file one side deleted and the other changed is a modify/delete
+    // conflict, as in git. Masked, the deletion would win silently, so the
+    // manifests are merged line by line instead and always reported.
+    let modified_and_deleted = ids.iter().any(|(resource, path)| {
+        let Some(base_content) = base_version.content(resource, path) else {
+            return false;
+        };
+        match (
+            our_version.content(resource, path),
+            their_version.content(resource, path),
+        ) {
+            (Some(only), None) | (None, Some(only)) => only != base_content,
+            _ => false,
+        }
+    });
+    if modified_and_deleted {
+        let mut outcome = line_merge(&options, base, ours, theirs);
+        outcome.conflicts = true;
+        return outcome;
+    }
+
+    let (mut text, mut conflicts) = match options.merge(
+        &base_version.masked,
+        &our_version.masked,
+        &their_version.masked,
+    ) {
+        Ok(text) => (text, false),
+        Err(text) => (text, true),
+    };
+
+    for (id, (resource, path)) in ids.iter().enumerate() {
+        let placeholder = placeholder(id);
+        if !text.contains(&placeholder) {
+            continue;
+        }
+
+        let base_content = base_version.content(resource, path).unwrap_or_default();
+        let our_content = our_version.content(resource, path);
+        let their_content = their_version.content(resource, path);
+
+        let (merged, conflicted) = match (our_content, their_content) {
+            (Some(ours), Some(theirs)) if ours == theirs => (ours.to_string(), false),
+            (Some(ours), Some(theirs)) if ours == base_content => (theirs.to_string(), false),
+            (Some(ours), Some(theirs)) if theirs == base_content => (ours.to_string(), false),
+            (Some(ours), Some(theirs)) => merge_lines(&options, base_content, ours, theirs),
+            (Some(only), None) | (None, Some(only)) => (only.to_string(), false),
+            (None, None) => continue,
+        };
+        conflicts |= conflicted;
+
+        let packed = pack(
+            &our_version,
+            &their_version,
+            resource,
+            path,
+            &merged,
+            conflicted,
+            peeler,
+        );
+        text = text.replace(&placeholder, &packed);
+    }
+
+    MergeOutcome { text, conflicts }
+}
+
+impl Version {
+    fn position(&self, resource: &str, path: &[PathSegment]) -> Option<usize> {
+        self.files
+            .iter()
+            .position(|file| file.resource == resource && file.path == path)
+    }
+
+    fn content(&self, resource: &str, path: &[PathSegment]) -> Option<&str> {
+        self.position(resource, path)
+            .map(|index| self.files[index].content.as_str())
+    }
+}
+
+/// Merges embedded file contents line by line. Missing final line breaks
+/// are added for the merge so conflict markers always start a new line.
+fn merge_lines(
+    options: &diffy::MergeOptions,
+    base: &str,
+    ours: &str,
+    theirs: &str,
+) -> (String, bool) {
+    let terminated = |text: &str| {
+        if text.is_empty() || text.ends_with('\n') {
+            text.to_string()
+        } else {
+            format!("{text}\n")
+        }
+    };
+    let (mut merged, conflicted) =
+        match options.merge(&terminated(base), &terminated(ours), &terminated(theirs)) {
+            Ok(merged) => (merged, false),
+            Err(merged) => (merged, true),
+        };
+    if !ours.ends_with('\n') && !theirs.ends_with('\n') && merged.ends_with('\n') {
+        merged.pop();
+    }
+    (merged, conflicted)
+}
+
+fn line_merge(options: &diffy::MergeOptions, base: &str, ours: &str, theirs: &str) -> MergeOutcome {
+    match options.merge(base, ours, theirs) {
+        Ok(text) => MergeOutcome {
+            text,
+            conflicts: false,
+        },
+        Err(text) => MergeOutcome {
+            text,
+            conflicts: true,
+        },
+    }
+}
+
+fn placeholder(id: usize) -> String {
+    format!("__rsp_embedded_{id}__")
+}
+
+fn mask(
+    peeler: &Peeler,
+    text: &str,
+    ids: &mut Vec<(String, Vec<PathSegment>)>,
+) -> Result<Version, RspError> {
+    let documents = manifest::load_documents(text)?;
+    let files = peeler.embedded_files(&documents)?;
+
+    let mut spans = Vec::new();
+    let mut double_escaped = Vec::new();
+    for file in &files {
+        let span = splice::locate(text, file.document, &file.path);
+        let raw = selector::lookup(&documents[file.document], &file.path)
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        double_escaped.push(raw != file.content);
+        spans.push(span);
+    }
+
+    // Files in flow collections cannot be located, and are merged with the
+    // lines around them.
+    let mut order: Vec<(usize, &ScalarSpan)> = spans
+        .iter()
+        .enumerate()
+        .filter_map(|(index, span)| Some((index, span.as_ref()?)))
+        .collect();
+    order.sort_by_key(|(_, span)| std::cmp::Reverse(span.start));
+
+    let mut masked = text.to_string();
+    for (index, span) in order {
+        let file = &files[index];
+        let pair = (file.resource.clone(), file.path.clone());
+        let id = match ids.iter().position(|existing| *existing == pair) {
+            Some(id) => id,
+            None => {
+                ids.push(pair);
+                ids.len() - 1
+            }
+        };
+        masked.replace_range(span.start..span.end, &placeholder(id));
+    }
+
+    Ok(Version {
+        masked,
+        files,
+        spans,
+        double_escaped,
+    })
+}
+
+/// Renders a merged embedded file in the style our side (or, for files only
+/// they have or could locate, their side) wrote it. Conflicted content is
+/// always written as a block scalar so the markers land on their own lines.
+fn pack(
+    ours: &Version,
+    theirs: &Version,
+    resource: &str,
+    path: &[PathSegment],
+    merged: &str,
+    conflicted: bool,
+    peeler: &Peeler,
+) -> String {
+    let located = |version| {
+        let index = Version::position(version, resource, path)?;
+        Some((version, index, version.spans[index].clone()?))
+    };
+    let (version, index, mut span) = located(ours)
+        .or_else(|| located(theirs))
+        .expect("placeholders stand for located files");
+
+    if conflicted {
+        span.style = ScalarStyle::Literal;
+        splice::render(&span, merged)
+    } else if version.double_escaped[index] {
+        splice::render(
+            &span,
+            &peeler.escape_value(&version.files[index].key, merged),
+        )
+    } else {
+        splice::render(&span, merged)
+    }
+}
@@ -0,0 +1,272 @@
+use crate::profiles::Profile;
+use crate::rules::TargetRule;
+use serde::Deserialize;
+use std::path::Path;
+
+/// The annotation `kubectl apply` stores the applied object in, as JSON.
+pub const LAST_APPLIED_ANNOTATION: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+/// What happens to JSON-valued annotations such as
+/// [`LAST_APPLIED_ANNOTATION`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationMode {
+    /// Leave them as they are.
+    #[default]
+    Keep,
+    /// Pretty-print them as block scalars.
+    Peel,
+    /// Remove them from the output.
+    Strip,
+}
+
+/// The syntax of the manifests themselves, as opposed to the files
+/// embedded in them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    /// One or more YAML documents.
+    Yaml,
+    /// One or more JSON values, such as `kubectl get -o json` output.
+    Json,
+    /// A TOML file. Only TOML input can be written back as TOML.
+    Toml,
+}
+
+impl DocumentFormat {
+    /// Guesses the format of `content` from its first line that is not
+    /// blank or a comment: TOML for a `[table]` header or a `key = value`
+    /// pair, JSON for other lines starting with `{` or `[`, and YAML
+    /// otherwise.
+    pub fn detect(content: &str) -> Self {
+        let line = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or_default();
+        if is_toml_table_header(line) || is_toml_key_value(line) {
+            DocumentFormat::Toml
+        } else if line.starts_with(['{', '[']) {
+            DocumentFormat::Json
+        } else {
+            DocumentFormat::Yaml
+        }
+    }
+
+    /// The format a file's extension names, if any.
+    pub fn for_path(path: &str) -> Option<Self> {
+        match Path::new(path).extension()?.to_str()? {
+            "yaml" | "yml" => Some(DocumentFormat::Yaml),
+            "json" => Some(DocumentFormat::Json),
+            "toml" => Some(DocumentFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+fn is_toml_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '))
+}
+
+/// `[table]` or `[[array.of.tables]]`, optionally followed by a comment.
+fn is_toml_table_header(line: &str) -> bool {
+    let line = line.split('#').next().unwrap_or_default().trim_end();
+    line.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(|inner| {
+            inner
+                .strip_prefix('[')
+                .and_then(|inner| inner.strip_suffix(']'))
+                .unwrap_or(inner)
+        })
+        .is_some_and(|key| is_toml_bare_key(key.trim()))
+}
+
+fn is_toml_key_value(line: &str) -> bool {
+    line.split_once('=')
+        .is_some_and(|(key, _)| is_toml_bare_key(key.trim()))
+}
+
+/// Options controlling how a [`Peeler`](crate::peeler::Peeler) peels
+/// manifests.
+///
+/// ```
+/// use rsp_cli::{PeelOptions, Peeler};
+///
+/// let peeler = Peeler::with_options(PeelOptions::new().key("config.json"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeelOptions {
+    pub(crate) unescape: bool,
+    pub(crate) keys: Vec<String>,
+    pub(crate) rules: Vec<TargetRule>,
+    pub(crate) builtin_rule: bool,
+    pub(crate) annotations: AnnotationMode,
+    pub(crate) annotation_keys: Vec<String>,
+    pub(crate) profiles: Vec<Profile>,
+    pub(crate) normalize: bool,
+    pub(crate) strip_dashboard_fields: bool,
+    pub(crate) template: bool,
+    pub(crate) input_format: Option<DocumentFormat>,
+    pub(crate) output_format: Option<DocumentFormat>,
+}
+
+impl Default for PeelOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PeelOptions {
+    pub fn new() -> Self {
+        Self {
+            unescape: true,
+            keys: Vec::new(),
+            rules: Vec::new(),
+            builtin_rule: true,
+            annotations: AnnotationMode::Keep,
+            annotation_keys: Vec::new(),
+            profiles: Profile::ALL
+                .iter()
+                .copied()
+                .filter(|profile| profile.enabled_by_default())
+                .collect(),
+            normalize: false,
+            strip_dashboard_fields: false,
+            template: false,
+            input_format: None,
+            output_format: None,
+        }
+    }
+
+    /// Whether backslash escapes left inside embedded files (`\n`, `\t`,
+    /// `\"`, ...) are turned into the characters they stand for. Enabled by
+    /// default; disable it to only re-render values as block scalars.
+    pub fn unescape(mut self, enabled: bool) -> Self {
+        self.unescape = enabled;
+        self
+    }
+
+    /// Restricts peeling to the given data key. Can be called several times;
+    /// when never called, every embedded file is peeled.
+    pub fn key(mut self, key: &str) -> Self {
+        self.keys.push(key.to_string());
+        self
+    }
+
+    /// Adds a rule choosing resources and paths to peel. Rules are tried
+    /// in the order they were added, and all of them before the built-in
+    /// rules of the enabled profiles and for ConfigMap `data`.
+    pub fn rule(mut self, rule: TargetRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Whether the built-in rule for ConfigMap `data` applies. Enabled by
+    /// default.
+    pub fn builtin_rule(mut self, enabled: bool) -> Self {
+        self.builtin_rule = enabled;
+        self
+    }
+
+    /// What happens to JSON annotations: the last-applied configuration
+    /// and those added with [`annotation`](PeelOptions::annotation). Left
+    /// alone by default.
+    pub fn annotations(mut self, mode: AnnotationMode) -> Self {
+        self.annotations = mode;
+        self
+    }
+
+    /// Treats this annotation as JSON too, besides the last-applied
+    /// configuration. Can be called several times.
+    pub fn annotation(mut self, key: &str) -> Self {
+        self.annotation_keys.push(key.to_string());
+        self
+    }
+
+    /// Turns a built-in [`Profile`] on or off. All but the Grafana
+    /// profile are on by default.
+    pub fn profile(mut self, profile: Profile, enabled: bool) -> Self {
+        self.profiles.retain(|existing| *existing != profile);
+        if enabled {
+            self.profiles.push(profile);
+        }
+        self
+    }
+
+    /// Whether every peeled file is rewritten in its format's canonical
+    /// layout, such as re-emitted YAML or pretty-printed JSON, as if each
+    /// rule set [`reformat`](TargetRule::reformat). Disabled by default.
+    pub fn normalize(mut self, enabled: bool) -> Self {
+        self.normalize = enabled;
+        self
+    }
+
+    /// Whether the `id`, `version` and `iteration` fields Grafana rewrites
+    /// on every save are removed from dashboards peeled by the `grafana`
+    /// profile. Disabled by default.
+    pub fn strip_dashboard_fields(mut self, enabled: bool) -> Self {
+        self.strip_dashboard_fields = enabled;
+        self
+    }
+
+    /// Whether input is read as a Helm chart template. Go template
+    /// directives are then kept as they are, and so is the rest of the
+    /// text apart from the peeled values. Disabled by default.
+    pub fn template(mut self, enabled: bool) -> Self {
+        self.template = enabled;
+        self
+    }
+
+    /// Reads input as this format instead of detecting it.
+    pub fn input_format(mut self, format: DocumentFormat) -> Self {
+        self.input_format = Some(format);
+        self
+    }
+
+    /// Writes output in this format instead of the input's. In JSON
+    /// output, embedded files cannot be block scalars, so embedded JSON is
+    /// pretty-printed instead. TOML output needs TOML input.
+    pub fn output_format(mut self, format: DocumentFormat) -> Self {
+        self.output_format = Some(format);
+        self
+    }
+
+    /// All rules in the order they are tried.
+    pub fn rules(&self) -> Vec<TargetRule> {
+        let mut rules = self.rules.clone();
+        if self.annotations == AnnotationMode::Peel {
+            for key in self.annotation_keys() {
+                let quoted = serde_json::Value::String(key.to_string());
+                rules.push(
+                    TargetRule::new()
+                        .path(&format!("metadata.annotations[{quoted}]"))
+                        .format("json")
+                        .reformat(true),
+                );
+            }
+        }
+        for profile in Profile::ALL {
+            if self.profiles.contains(profile) {
+                rules.extend(profile.rules());
+            }
+        }
+        if self.builtin_rule {
+            rules.push(TargetRule::configmap_data());
+        }
+        rules
+    }
+
+    /// The annotations holding JSON, the last-applied one first.
+    pub(crate) fn annotation_keys(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(LAST_APPLIED_ANNOTATION)
+            .chain(self.annotation_keys.iter().map(String::as_str))
+    }
+
+    pub(crate) fn allows_key(&self, key: &str) -> bool {
+        self.keys.is_empty() || self.keys.iter().any(|allowed| allowed == key)
+    }
+}
@@ -1,9 +1,40 @@
+use crate::emitter::Emitter;
 use crate::error::RspError;
-use serde_yaml::{Mapping, Value};
-use std::fs;
-use std::io::{self, Read};
+use crate::format::{self, EmbeddedFormat, FormatRegistry, GrafanaDashboard};
+use crate::kustomize::Literal;
+use crate::manifest;
+use crate::options::{AnnotationMode, DocumentFormat, PeelOptions};
+use crate::report::{EntryStatus, PeelOutput, PeelReport, ReportEntry};
+use crate::rules::{self, Slot};
+use crate::scalar;
+use crate::selector::{self, PathSegment};
+use crate::splice::{self, ScalarStyle};
+use crate::template;
+use crate::toml_document;
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_yaml::Value;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
 
-pub struct Peeler;
+pub struct Peeler {
+    options: PeelOptions,
+    formats: FormatRegistry,
+}
+
+/// One embedded file found in a manifest, with its content unescaped the
+/// same way `peel` would.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedFile {
+    /// Index of the document holding the file.
+    pub document: usize,
+    /// The owning resource as `Kind/namespace/name`.
+    pub resource: String,
+    /// The path from the document root to the value.
+    pub path: Vec<PathSegment>,
+    pub key: String,
+    pub content: String,
+}
 
 impl Default for Peeler {
     fn default() -> Self {
@@ -13,197 +44,794 @@ impl Default for Peeler {
 
 impl Peeler {
     pub fn new() -> Self {
-        Self
+        Self::with_options(PeelOptions::default())
     }
 
-    pub fn peel_file(
-        &self,
-        input_file: &str,
-        output_file: Option<&String>,
-    ) -> Result<(), RspError> {
-        let content = fs::read_to_string(input_file)
-            .map_err(|_| RspError::FileNotFound(input_file.to_string()))?;
+    pub fn with_options(options: PeelOptions) -> Self {
+        let mut formats = FormatRegistry::new();
+        if options.strip_dashboard_fields {
+            formats.register(GrafanaDashboard::new().strip_volatile(true));
+        }
+        Self { options, formats }
+    }
 
-        self.peel_content(&content, output_file)
+    pub fn options(&self) -> &PeelOptions {
+        &self.options
     }
 
-    pub fn peel_stdin(&self, output_file: Option<&String>) -> Result<(), RspError> {
-        let mut content = String::new();
-        io::stdin()
-            .read_to_string(&mut content)
-            .map_err(|e| RspError::Processing(format!("Failed to read from stdin: {e}")))?;
+    /// The embedded file formats this peeler recognizes.
+    pub fn formats(&self) -> &FormatRegistry {
+        &self.formats
+    }
 
-        self.peel_content(&content, output_file)
+    /// Adds an embedded file format, taking precedence over the ones
+    /// already known.
+    pub fn register_format(&mut self, format: impl EmbeddedFormat + 'static) {
+        self.formats.register(format);
     }
 
-    fn peel_content(&self, content: &str, output_file: Option<&String>) -> Result<(), RspError> {
-        let mut yaml_value: Value = serde_yaml::from_str(content)?;
+    pub fn peel_file(
+        &self,
+        input_file: &str,
+        output_file: Option<&String>,
+    ) -> Result<PeelReport, RspError> {
+        let file =
+            File::open(input_file).map_err(|_| RspError::FileNotFound(input_file.to_string()))?;
 
-        self.process_yaml_value(&mut yaml_value)?;
+        // The extension tells TOML apart better than the content can.
+        match DocumentFormat::for_path(input_file) {
+            Some(format) if self.options.input_format.is_none() => self
+                .reconfigured(self.options.clone().input_format(format))
+                .peel_to_output(file, output_file),
+            _ => self.peel_to_output(file, output_file),
+        }
+    }
 
-        let output = self.serialize_yaml_with_pipes(&yaml_value)?;
+    pub fn peel_stdin(&self, output_file: Option<&String>) -> Result<PeelReport, RspError> {
+        self.peel_to_output(io::stdin().lock(), output_file)
+    }
 
+    fn peel_to_output<R: Read>(
+        &self,
+        reader: R,
+        output_file: Option<&String>,
+    ) -> Result<PeelReport, RspError> {
         match output_file {
             Some(file_path) => {
+                // Peel fully before touching the output so a failure leaves
+                // no truncated file behind.
+                let mut output = Vec::new();
+                let report = self.peel_reader(reader, &mut output)?;
                 fs::write(file_path, output)?;
-                println!("Output written to {file_path}");
-            }
-            None => {
-                print!("{output}");
+                Ok(report)
             }
+            None => self.peel_reader(reader, io::stdout().lock()),
         }
+    }
 
-        Ok(())
+    /// Peels every document of `content` and returns the rendered YAML with
+    /// a report of what was peeled, without any other side effect.
+    pub fn peel_str(&self, content: &str) -> Result<PeelOutput, RspError> {
+        let mut output = Vec::new();
+        let report = self.peel_documents(content, &mut output)?;
+        let text = String::from_utf8(output)
+            .map_err(|e| RspError::Processing(format!("emitter wrote invalid UTF-8: {e}")))?;
+        Ok(PeelOutput { text, report })
     }
 
-    pub fn process_yaml_value(&self, value: &mut Value) -> Result<(), RspError> {
-        match value {
-            Value::Mapping(map) => {
-                self.process_configmap(map)?;
-            }
-            _ => {
+    /// Peels the manifest read from `reader` and streams the rendered YAML
+    /// to `writer`.
+    ///
+    /// The input is read to the end before anything is written, since a
+    /// document can only be peeled once it has been parsed as a whole.
+    pub fn peel_reader<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        writer: W,
+    ) -> Result<PeelReport, RspError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        self.peel_documents(&content, writer)
+    }
+
+    fn peel_documents<W: Write>(
+        &self,
+        content: &str,
+        mut writer: W,
+    ) -> Result<PeelReport, RspError> {
+        if self.options.template {
+            let output = self.peel_template(content)?;
+            writer.write_all(output.text.as_bytes())?;
+            writer.flush()?;
+            return Ok(output.report);
+        }
+
+        let (documents, input_format) = self.load_input(content)?;
+        if documents.is_empty() {
+            return Err(RspError::InvalidFormat(
+                "No YAML documents found".to_string(),
+            ));
+        }
+
+        // Output is in the input's format unless chosen otherwise.
+        let output_format = self.options.output_format.unwrap_or(input_format);
+        if output_format == DocumentFormat::Toml {
+            if input_format != DocumentFormat::Toml {
                 return Err(RspError::InvalidFormat(
-                    "Expected YAML mapping at root level".to_string(),
+                    "TOML output needs TOML input".to_string(),
                 ));
             }
+            let output = self.peel_toml(content)?;
+            writer.write_all(output.text.as_bytes())?;
+            writer.flush()?;
+            return Ok(output.report);
         }
-        Ok(())
+        if self.options.output_format.is_none() {
+            return self
+                .reconfigured(self.options.clone().output_format(output_format))
+                .write_documents(documents, writer);
+        }
+        self.write_documents(documents, writer)
     }
 
-    fn process_configmap(&self, map: &mut Mapping) -> Result<(), RspError> {
-        if let Some(Value::String(kind)) = map.get(Value::String("kind".to_string())) {
-            if kind == "ConfigMap" {
-                if let Some(Value::Mapping(data_map)) =
-                    map.get_mut(Value::String("data".to_string()))
-                {
-                    self.process_data_section(data_map)?;
+    /// A peeler with the same formats as this one and other options.
+    fn reconfigured(&self, options: PeelOptions) -> Peeler {
+        Peeler {
+            options,
+            formats: self.formats.clone(),
+        }
+    }
+
+    /// Parses `content` as the configured input format, or the detected
+    /// one, and returns the documents with the format they were read as.
+    fn load_input(&self, content: &str) -> Result<(Vec<Value>, DocumentFormat), RspError> {
+        let format = self
+            .options
+            .input_format
+            .unwrap_or_else(|| DocumentFormat::detect(content));
+        match format {
+            DocumentFormat::Json => match manifest::load_json_documents(content) {
+                Ok(documents) => Ok((documents, DocumentFormat::Json)),
+                // A YAML flow mapping starts with `{` as well, so detected
+                // formats fall back to YAML.
+                Err(_) if self.options.input_format.is_none() => {
+                    Ok((manifest::load_documents(content)?, DocumentFormat::Yaml))
                 }
+                Err(e) => Err(e),
+            },
+            DocumentFormat::Yaml => Ok((manifest::load_documents(content)?, DocumentFormat::Yaml)),
+            DocumentFormat::Toml => match toml_document::load(content) {
+                Ok(document) => Ok((vec![document], DocumentFormat::Toml)),
+                Err(_) if self.options.input_format.is_none() => {
+                    Ok((manifest::load_documents(content)?, DocumentFormat::Yaml))
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    /// Peels `documents` and writes them in the output format.
+    fn write_documents<W: Write>(
+        &self,
+        mut documents: Vec<Value>,
+        mut writer: W,
+    ) -> Result<PeelReport, RspError> {
+        let mut report = PeelReport::default();
+        for (index, document) in documents.iter_mut().enumerate() {
+            self.process_document(document, index, &mut report)?;
+        }
+
+        if self.options.output_format == Some(DocumentFormat::Json) {
+            for document in &documents {
+                // Indented like `kubectl get -o json`.
+                let formatter = PrettyFormatter::with_indent(b"    ");
+                let mut serializer = serde_json::Serializer::with_formatter(&mut writer, formatter);
+                document
+                    .serialize(&mut serializer)
+                    .map_err(|e| RspError::Processing(format!("cannot write JSON: {e}")))?;
+                writer.write_all(b"\n")?;
             }
+            writer.flush()?;
+            return Ok(report);
         }
-        Ok(())
+
+        let mut emitter = Emitter::new(writer);
+        for (index, document) in documents.iter().enumerate() {
+            if index > 0 {
+                emitter.separator()?;
+            }
+            emitter.document(document)?;
+        }
+        emitter.into_inner().flush()?;
+
+        Ok(report)
     }
 
-    fn process_data_section(&self, data_map: &mut Mapping) -> Result<(), RspError> {
-        let keys_to_process: Vec<_> = data_map
-            .iter()
-            .filter_map(|(key, value)| {
-                if let (Value::String(key_str), Value::String(value_str)) = (key, value) {
-                    if self.should_process_key(key_str) {
-                        Some((key.clone(), value_str.clone()))
-                    } else {
-                        None
+    /// Peels a Helm chart template in place: its directives are masked so
+    /// it parses as YAML, and only the peeled values are rewritten, so the
+    /// rest of the template is kept byte for byte.
+    fn peel_template(&self, content: &str) -> Result<PeelOutput, RspError> {
+        let masked = template::mask(content)?;
+        let documents = manifest::load_documents(&masked.text)?;
+        if documents.is_empty() {
+            return Err(RspError::InvalidFormat(
+                "No YAML documents found".to_string(),
+            ));
+        }
+
+        let rules = self.options.rules();
+        let mut report = PeelReport::default();
+        let mut edits = Vec::new();
+        for (index, document) in documents.iter().enumerate() {
+            let mut peeled = document.clone();
+            let first_entry = report.entries.len();
+            self.process_document(&mut peeled, index, &mut report)?;
+
+            for (prefix, resource) in resources(document) {
+                for slot in rules::slots(&rules, resource)? {
+                    let path = [prefix.as_slice(), &slot.path].concat();
+                    let Some(Value::String(value)) = selector::lookup(&peeled, &path) else {
+                        continue;
+                    };
+                    let display = selector::display_path(&path);
+                    let entry = report.entries[first_entry..]
+                        .iter_mut()
+                        .find(|entry| entry.path == display);
+                    // A file holding directives cannot be valid until
+                    // rendered, so validation warnings would only be noise.
+                    if template::contains_placeholder(value)
+                        && let Some(entry) = entry
+                    {
+                        entry.warnings.clear();
+                    }
+                    let unchanged =
+                        selector::lookup(document, &path) == Some(&Value::String(value.clone()));
+                    if unchanged && !value.contains('\n') {
+                        continue;
+                    }
+
+                    match splice::locate(&masked.text, index, &path) {
+                        // A multi-line value still written as a quoted
+                        // string is turned into a block even if unchanged.
+                        Some(span)
+                            if unchanged
+                                && matches!(
+                                    span.style,
+                                    ScalarStyle::Literal | ScalarStyle::Folded
+                                ) => {}
+                        Some(mut span) => {
+                            if value.contains('\n') {
+                                span.style = ScalarStyle::Literal;
+                            }
+                            edits.push((span, value.clone()));
+                        }
+                        // Nothing to write for a value peeling left as it
+                        // was, even where it cannot be located.
+                        None if unchanged => {}
+                        None => {
+                            if let Some(entry) = report.entries[first_entry..]
+                                .iter_mut()
+                                .find(|entry| entry.path == display)
+                            {
+                                entry.status = EntryStatus::Failed {
+                                    error: "could not locate the value in the template".to_string(),
+                                };
+                                entry.bytes_after = entry.bytes_before;
+                            }
+                        }
                     }
-                } else {
-                    None
                 }
-            })
-            .collect();
+            }
+        }
+
+        edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+        let mut text = masked.text.clone();
+        for (span, value) in edits {
+            text = splice::replace(&text, &span, &value);
+        }
+        for entry in &mut report.entries {
+            entry.resource = masked.restore(&entry.resource);
+            entry.name = entry.name.as_deref().map(|name| masked.restore(name));
+            entry.namespace = entry
+                .namespace
+                .as_deref()
+                .map(|namespace| masked.restore(namespace));
+        }
+        Ok(PeelOutput {
+            text: masked.restore(&text),
+            report,
+        })
+    }
+
+    /// Peels a TOML file in place: only the peeled strings are rewritten,
+    /// so comments and the order of tables and keys are kept.
+    fn peel_toml(&self, content: &str) -> Result<PeelOutput, RspError> {
+        let original = toml_document::load(content)?;
+        let mut document = toml_document::parse(content)?;
 
-        for (key, value_str) in keys_to_process {
-            let processed = self.process_raw_string(&value_str)?;
-            data_map.insert(key, Value::String(processed));
+        let mut peeled = original.clone();
+        let mut report = PeelReport::default();
+        self.process_document(&mut peeled, 0, &mut report)?;
+
+        for slot in rules::slots(&self.options.rules(), &original)? {
+            let Some(Value::String(value)) = selector::lookup(&peeled, &slot.path) else {
+                continue;
+            };
+            let unchanged =
+                selector::lookup(&original, &slot.path) == Some(&Value::String(value.clone()));
+            if unchanged && !value.contains('\n') {
+                continue;
+            }
+            if !toml_document::set_string(&mut document, &slot.path, value) {
+                let display = selector::display_path(&slot.path);
+                if let Some(entry) = report
+                    .entries
+                    .iter_mut()
+                    .find(|entry| entry.path == display)
+                {
+                    entry.status = EntryStatus::Failed {
+                        error: "could not locate the value in the TOML file".to_string(),
+                    };
+                    entry.bytes_after = entry.bytes_before;
+                }
+            }
         }
 
-        Ok(())
+        Ok(PeelOutput {
+            text: document.to_string(),
+            report,
+        })
     }
 
-    pub fn should_process_key(&self, key: &str) -> bool {
-        key.ends_with(".yaml")
-            || key.ends_with(".yml")
-            || key.ends_with(".json")
-            || key.ends_with(".toml")
+    /// Returns the unescaped content of the ConfigMap `data` entry `key`,
+    /// optionally selecting the ConfigMap by `metadata.name`.
+    pub fn extract(
+        &self,
+        content: &str,
+        key: &str,
+        name: Option<&str>,
+    ) -> Result<String, RspError> {
+        let documents = manifest::load_documents(content)?;
+        let (_, raw) = manifest::find_data_value(&documents, key, name)?;
+        self.process_raw_string(key, raw)
     }
 
-    fn process_raw_string(&self, raw_string: &str) -> Result<String, RspError> {
-        let unescaped = self.unescape_string(raw_string)?;
-        Ok(unescaped)
+    /// Replaces the content of the ConfigMap `data` entry `key` with
+    /// `new_value`, the counterpart of [`Peeler::extract`].
+    ///
+    /// Only the value itself is rewritten, in the style (escaped or block) it
+    /// already had; the rest of `content` is returned untouched.
+    pub fn replace(
+        &self,
+        content: &str,
+        key: &str,
+        name: Option<&str>,
+        new_value: &str,
+    ) -> Result<String, RspError> {
+        let documents = manifest::load_documents(content)?;
+        let (index, raw) = manifest::find_data_value(&documents, key, name)?;
+
+        // Values that were escaped twice are written back the same way.
+        let new_raw = if self.process_raw_string(key, raw)? != raw {
+            self.escape_value(key, new_value)
+        } else {
+            new_value.to_string()
+        };
+
+        let path = [
+            PathSegment::Key("data".to_string()),
+            PathSegment::Key(key.to_string()),
+        ];
+        let span = splice::locate(content, index, &path).ok_or_else(|| {
+            RspError::Processing(format!("could not locate data key '{key}' in the file"))
+        })?;
+        let updated = splice::replace(content, &span, &new_raw);
+
+        let documents = manifest::load_documents(&updated)?;
+        match manifest::find_data_value(&documents, key, name) {
+            Ok((_, written)) if written == new_raw => Ok(updated),
+            _ => Err(RspError::Processing(format!(
+                "failed to write data key '{key}' back"
+            ))),
+        }
     }
 
-    pub fn unescape_string(&self, escaped: &str) -> Result<String, RspError> {
-        let mut result = String::new();
-        let mut chars = escaped.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '\\' {
-                match chars.next() {
-                    Some('n') => result.push('\n'),
-                    Some('t') => result.push('\t'),
-                    Some('r') => result.push('\r'),
-                    Some('\\') => result.push('\\'),
-                    Some('"') => result.push('"'),
-                    Some(c) => {
-                        result.push('\\');
-                        result.push(c);
-                    }
-                    None => result.push('\\'),
+    /// Lists the string values claimed by the target rules in every
+    /// document.
+    pub fn embedded_files(&self, documents: &[Value]) -> Result<Vec<EmbeddedFile>, RspError> {
+        let rules = self.options.rules();
+        let mut files = Vec::new();
+        for (index, document) in documents.iter().enumerate() {
+            for (prefix, resource) in resources(document) {
+                for slot in rules::slots(&rules, resource)? {
+                    let Some(Value::String(raw)) = selector::lookup(resource, &slot.path) else {
+                        continue;
+                    };
+                    let literal = slot.literal.then(|| Literal::parse(raw)).flatten();
+                    let raw = literal.as_ref().map_or(raw, |literal| &literal.value);
+                    let content = match self.slot_format(&slot, raw)? {
+                        Some(format) => self.unescape_with(format, raw)?,
+                        None => raw.clone(),
+                    };
+                    files.push(EmbeddedFile {
+                        document: index,
+                        resource: manifest::resource_id(resource),
+                        path: [prefix.as_slice(), &slot.path].concat(),
+                        key: slot.key,
+                        content,
+                    });
                 }
-            } else {
-                result.push(ch);
             }
         }
+        Ok(files)
+    }
 
-        Ok(result)
+    pub fn process_yaml_value(&self, value: &mut Value) -> Result<(), RspError> {
+        self.process_document(value, 0, &mut PeelReport::default())
     }
 
-    pub fn serialize_yaml_with_pipes(&self, value: &Value) -> Result<String, RspError> {
-        let mut output = String::new();
-        self.serialize_value(value, &mut output, 0, &mut std::collections::HashSet::new())?;
-        Ok(output)
+    /// Peels one parsed document in place and reports what changed.
+    pub fn peel_value(&self, document: &mut Value) -> Result<PeelReport, RspError> {
+        let mut report = PeelReport::default();
+        self.process_document(document, 0, &mut report)?;
+        Ok(report)
     }
 
-    fn serialize_value(
-        &self,
-        value: &Value,
-        output: &mut String,
-        indent: usize,
-        _processed_keys: &mut std::collections::HashSet<String>,
-    ) -> Result<(), RspError> {
-        match value {
-            Value::Mapping(map) => {
-                for (key, val) in map {
-                    if let Value::String(key_str) = key {
-                        self.write_indent(output, indent);
-                        output.push_str(&format!("{key_str}:"));
-
-                        if let Value::String(string_val) = val {
-                            if self.should_process_key(key_str) && string_val.contains('\n') {
-                                output.push_str(" |\n");
-                                for line in string_val.lines() {
-                                    self.write_indent(output, indent + 1);
-                                    output.push_str(line);
-                                    output.push('\n');
-                                }
-                            } else {
-                                output.push(' ');
-                                self.serialize_value(val, output, 0, _processed_keys)?;
-                                output.push('\n');
-                            }
-                        } else {
-                            output.push('\n');
-                            self.serialize_value(val, output, indent + 1, _processed_keys)?;
-                        }
+    /// Turns the embedded files of one parsed document back into escaped
+    /// strings, the inverse of peeling it. Files of no known format are
+    /// left alone.
+    pub fn pack_value(&self, document: &mut Value) -> Result<(), RspError> {
+        if !document.is_mapping() {
+            return Err(RspError::InvalidFormat(
+                "Expected YAML mapping at root level".to_string(),
+            ));
+        }
+
+        for slot in rules::slots(&self.options.rules(), document)? {
+            if !self.options.allows_key(&slot.key) {
+                continue;
+            }
+            let Some(Value::String(content)) = selector::lookup_mut(document, &slot.path) else {
+                continue;
+            };
+            match slot.literal.then(|| Literal::parse(content)).flatten() {
+                Some(literal) => {
+                    if let Some(format) = self.slot_format(&slot, &literal.value)? {
+                        *content = literal.with_value(&format.escape(&literal.value));
+                    }
+                }
+                None => {
+                    if let Some(format) = self.slot_format(&slot, content)? {
+                        *content = format.escape(content);
                     }
                 }
             }
-            Value::String(s) => {
-                if s.contains('\n') || s.contains('"') || s.starts_with(' ') || s.ends_with(' ') {
-                    output.push_str(&format!("\"{}\"", s.replace('"', "\\\"")));
-                } else {
-                    output.push_str(s);
+        }
+        Ok(())
+    }
+
+    fn process_document(
+        &self,
+        value: &mut Value,
+        index: usize,
+        report: &mut PeelReport,
+    ) -> Result<(), RspError> {
+        if !value.is_mapping() {
+            return Err(RspError::InvalidFormat(
+                "Expected YAML mapping at root level".to_string(),
+            ));
+        }
+
+        if manifest::is_list(value)
+            && let Some(Value::Sequence(items)) = value.get_mut("items")
+        {
+            for (item_index, item) in items.iter_mut().enumerate() {
+                if item.is_mapping() {
+                    let prefix = [
+                        PathSegment::Key("items".to_string()),
+                        PathSegment::Index(item_index),
+                    ];
+                    self.process_resource(item, index, &prefix, report)?;
                 }
             }
-            _ => {
-                let serialized = serde_yaml::to_string(value)?;
-                output.push_str(serialized.trim());
-                if !serialized.ends_with('\n') {
-                    output.push('\n');
+            return Ok(());
+        }
+        self.process_resource(value, index, &[], report)
+    }
+
+    /// Peels one resource, found at `prefix` in document `index`.
+    fn process_resource(
+        &self,
+        value: &mut Value,
+        index: usize,
+        prefix: &[PathSegment],
+        report: &mut PeelReport,
+    ) -> Result<(), RspError> {
+        let display_path = |path: &[PathSegment]| selector::display_path(&[prefix, path].concat());
+        let template = ReportEntry {
+            document: index,
+            kind: manifest::kind(value).map(str::to_string),
+            namespace: manifest::namespace(value).map(str::to_string),
+            name: manifest::name(value).map(str::to_string),
+            resource: manifest::resource_id(value),
+            path: String::new(),
+            key: String::new(),
+            format: None,
+            status: EntryStatus::Peeled,
+            bytes_before: 0,
+            bytes_after: 0,
+            warnings: Vec::new(),
+        };
+
+        if self.options.annotations == AnnotationMode::Strip {
+            manifest::strip_annotations(value, self.options.annotation_keys());
+        }
+
+        for slot in rules::slots(&self.options.rules(), value)? {
+            let mut entry = ReportEntry {
+                path: display_path(&slot.path),
+                key: slot.key.clone(),
+                ..template.clone()
+            };
+            if let Some(raw) = selector::lookup_mut(value, &slot.path) {
+                self.process_slot(&slot, raw, &mut entry)?;
+            }
+            report.entries.push(entry);
+        }
+
+        if manifest::is_configmap(value)
+            && let Some(Value::Mapping(binary_map)) = value.get("binaryData")
+        {
+            for (key, data) in binary_map {
+                let size = data.as_str().map_or(0, str::len);
+                report.entries.push(ReportEntry {
+                    path: display_path(&[
+                        PathSegment::Key("binaryData".to_string()),
+                        PathSegment::Key(key.as_str().unwrap_or_default().to_string()),
+                    ]),
+                    key: key.as_str().unwrap_or_default().to_string(),
+                    status: EntryStatus::Skipped {
+                        reason: "binary data".to_string(),
+                    },
+                    bytes_before: size,
+                    bytes_after: size,
+                    ..template.clone()
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Peels the value in one slot, recording what happened in `entry`.
+    fn process_slot(
+        &self,
+        slot: &Slot,
+        value: &mut Value,
+        entry: &mut ReportEntry,
+    ) -> Result<(), RspError> {
+        let Value::String(raw) = value else {
+            entry.status = EntryStatus::Skipped {
+                reason: "value is not a string".to_string(),
+            };
+            return Ok(());
+        };
+        match slot.literal.then(|| Literal::parse(raw)).flatten() {
+            Some(literal) => {
+                let mut content = literal.value.clone();
+                self.process_file(slot, &mut content, entry)?;
+                *raw = literal.with_value(&content);
+                Ok(())
+            }
+            None => self.process_file(slot, raw, entry),
+        }
+    }
+
+    /// Peels the content of one embedded file.
+    fn process_file(
+        &self,
+        slot: &Slot,
+        raw: &mut String,
+        entry: &mut ReportEntry,
+    ) -> Result<(), RspError> {
+        entry.bytes_before = raw.len();
+        entry.bytes_after = raw.len();
+
+        let Some(format) = self.slot_format(slot, raw)? else {
+            let reason = if slot.explicit && slot.multi_line_only {
+                "single-line text"
+            } else {
+                "unsupported file type"
+            };
+            entry.status = EntryStatus::Skipped {
+                reason: reason.to_string(),
+            };
+            return Ok(());
+        };
+        entry.format = Some(format.name().to_string());
+
+        if !self.options.allows_key(&slot.key) {
+            entry.status = EntryStatus::Skipped {
+                reason: "not selected".to_string(),
+            };
+            return Ok(());
+        }
+
+        // JSON output has no block scalars, so embedded JSON is made
+        // readable by pretty-printing it instead.
+        let json_output = self.options.output_format == Some(DocumentFormat::Json);
+        let pretty_print = json_output && format.name() == "json";
+        // Plain text has no canonical layout, so reformatting it only
+        // unescapes it.
+        let reformat = slot.reformat || self.options.normalize || pretty_print;
+        let processed = if reformat && format.name() != "text" {
+            self.reformat_with(format, raw)
+        } else if slot.multi_line_only && !is_escaped_script(raw) {
+            // Taken as stored: a backslash in `printf '%s\n'` or a regex
+            // is no escape.
+            Ok(raw.to_string())
+        } else {
+            self.unescape_with(format, raw)
+        };
+        match processed {
+            Ok(processed) => {
+                entry.bytes_after = processed.len();
+                if let Err(RspError::Validation(message)) =
+                    format::validate_as(format, &slot.key, &processed)
+                {
+                    entry.warnings.push(message);
+                }
+                if !json_output
+                    && processed.contains('\n')
+                    && !scalar::is_block_compatible(&processed)
+                {
+                    entry
+                        .warnings
+                        .push("contains control characters, kept as a quoted string".to_string());
                 }
+                *raw = processed;
+            }
+            Err(e) => {
+                entry.status = EntryStatus::Failed {
+                    error: e.to_string(),
+                };
             }
         }
         Ok(())
     }
 
-    fn write_indent(&self, output: &mut String, indent: usize) {
-        output.push_str(&"  ".repeat(indent));
+    /// The format of the file in `slot`: the one its rule names, the one
+    /// detected from its key or content, or plain text for strings a rule
+    /// selected directly. For rules that ask, a detected format must parse
+    /// the string as stored, and text must span several lines or be an
+    /// escaped script.
+    fn slot_format(&self, slot: &Slot, raw: &str) -> Result<Option<&dyn EmbeddedFormat>, RspError> {
+        match &slot.format {
+            Some(name) => self.formats.get(name).map(Some).ok_or_else(|| {
+                RspError::InvalidFormat(format!("unknown format '{name}' in target rule"))
+            }),
+            None if slot.multi_line_only => {
+                let text = || {
+                    (slot.explicit && (raw.contains('\n') || is_escaped_script(raw)))
+                        .then(|| self.formats.get("text"))
+                        .flatten()
+                };
+                Ok(match self.detect(slot, raw) {
+                    Some(format) if format.validate(raw).is_ok() => Some(format),
+                    _ => text(),
+                })
+            }
+            None => Ok(self
+                .detect(slot, raw)
+                .or_else(|| slot.explicit.then(|| self.formats.get("text")).flatten())),
+        }
+    }
+
+    /// The format detected for `slot` from its key, and from its content
+    /// only for rules that ask.
+    fn detect(&self, slot: &Slot, raw: &str) -> Option<&dyn EmbeddedFormat> {
+        if slot.detect_content {
+            self.formats.detect(&slot.key, raw)
+        } else {
+            self.formats.for_key(&slot.key)
+        }
+    }
+
+    pub fn should_process_key(&self, key: &str) -> bool {
+        self.formats.for_key(key).is_some()
+    }
+
+    /// Turns the stored value of `key` into file content, the way `peel`
+    /// would. Values of unrecognized files are returned unchanged.
+    fn process_raw_string(&self, key: &str, raw_string: &str) -> Result<String, RspError> {
+        match self.formats.for_key(key) {
+            Some(format) => self.unescape_with(format, raw_string),
+            None => Ok(raw_string.to_string()),
+        }
+    }
+
+    fn unescape_with(
+        &self,
+        format: &dyn EmbeddedFormat,
+        raw_string: &str,
+    ) -> Result<String, RspError> {
+        if !self.options.unescape {
+            return Ok(raw_string.to_string());
+        }
+        format.unescape(raw_string)
+    }
+
+    /// Rewrites `raw` in the format's canonical layout. It is unescaped
+    /// first only when it does not parse as stored, so JSON held directly
+    /// in a string keeps the escapes inside its own strings.
+    fn reformat_with(&self, format: &dyn EmbeddedFormat, raw: &str) -> Result<String, RspError> {
+        format
+            .format(raw)
+            .or_else(|_| format.format(&self.unescape_with(format, raw)?))
+    }
+
+    /// Escapes `content` the way the format of the file stored under `key`
+    /// expects, the counterpart of unescaping it.
+    pub fn escape_value(&self, key: &str, content: &str) -> String {
+        match self.formats.for_key(key) {
+            Some(format) => format.escape(content),
+            None => self.escape_string(content),
+        }
+    }
+
+    pub fn unescape_string(&self, escaped: &str) -> Result<String, RspError> {
+        Ok(format::unescape(escaped))
+    }
+
+    /// Escapes a string so that [`Peeler::unescape_string`] restores it.
+    pub fn escape_string(&self, unescaped: &str) -> String {
+        format::escape(unescaped)
+    }
+
+    pub fn serialize_yaml_with_pipes(&self, value: &Value) -> Result<String, RspError> {
+        let mut output = Vec::new();
+        self.write_yaml_with_pipes(value, &mut output)?;
+        String::from_utf8(output)
+            .map_err(|e| RspError::Processing(format!("emitter wrote invalid UTF-8: {e}")))
+    }
+
+    /// Streaming counterpart of [`Peeler::serialize_yaml_with_pipes`].
+    pub fn write_yaml_with_pipes<W: Write>(
+        &self,
+        value: &Value,
+        writer: W,
+    ) -> Result<(), RspError> {
+        let mut emitter = Emitter::new(writer);
+        emitter.document(value)?;
+        emitter.into_inner().flush()?;
+        Ok(())
+    }
+}
+
+/// Whether `raw` is a script escaped onto one line, such as
+/// `#!/bin/sh\necho hi`: a shebang line cannot be followed by more code on
+/// the same line, so its `\n` is an escape.
+fn is_escaped_script(raw: &str) -> bool {
+    raw.starts_with("#!") && !raw.contains('\n') && raw.contains("\\n")
+}
+
+/// The resources in `document`: the items of a `List`, or the document
+/// itself, with the path leading to each.
+fn resources(document: &Value) -> Vec<(Vec<PathSegment>, &Value)> {
+    match document.get("items") {
+        Some(Value::Sequence(items)) if manifest::is_list(document) => items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.is_mapping())
+            .map(|(index, item)| {
+                (
+                    vec![
+                        PathSegment::Key("items".to_string()),
+                        PathSegment::Index(index),
+                    ],
+                    item,
+                )
+            })
+            .collect(),
+        _ => vec![(Vec::new(), document)],
     }
 }
@@ -0,0 +1,179 @@
+//! The `kubectl-rsp` kubectl plugin.
+//!
+//! `kubectl rsp get cm my-config -n prod` runs `kubectl get` with the same
+//! arguments and `-o yaml`, and prints the result peeled. Without `get`,
+//! the plugin peels `kubectl get -o yaml` output read from `--filename` or
+//! stdin. Either way it takes the options of `rsp peel` and behaves the
+//! same.
+
+use crate::cli::{configured_arg, peel_args, peel_options, print_report};
+use crate::error::RspError;
+use crate::options::DocumentFormat;
+use crate::peeler::Peeler;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::io;
+use std::process::{self, Stdio};
+
+/// Environment variable naming the `kubectl` binary to run, `kubectl` on
+/// the `PATH` by default.
+pub const KUBECTL_ENV: &str = "KUBECTL";
+
+pub struct KubectlPlugin;
+
+impl Default for KubectlPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KubectlPlugin {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn run(&self) -> Result<(), RspError> {
+        let matches = self.build_cli().get_matches();
+
+        match matches.subcommand() {
+            Some(("get", sub_matches)) => self.handle_get_command(sub_matches),
+            _ => self.handle_input(&matches),
+        }
+    }
+
+    fn build_cli(&self) -> Command {
+        Command::new("kubectl-rsp")
+            .about("Peel embedded files of Kubernetes resources, as a kubectl plugin")
+            .version(env!("CARGO_PKG_VERSION"))
+            .arg(configured_arg())
+            // Short flags such as `-k` mean something else to kubectl, so
+            // the `rsp peel` options are only taken in their long form.
+            .args(
+                peel_args()
+                    .into_iter()
+                    .map(|arg| arg.short(None).global(true)),
+            )
+            .arg(
+                Arg::new("filename")
+                    .short('f')
+                    .long("filename")
+                    .help("File with `kubectl get -o yaml` output (default: stdin)")
+                    .value_name("FILE"),
+            )
+            .subcommand(
+                Command::new("get")
+                    .about("Run `kubectl get -o yaml` and peel its output")
+                    .arg(
+                        Arg::new("resource")
+                            .help("The resource type, such as cm or configmaps")
+                            .required(true)
+                            .value_name("TYPE"),
+                    )
+                    .arg(
+                        Arg::new("names")
+                            .help("Names of the resources (default: all)")
+                            .num_args(0..)
+                            .value_name("NAME"),
+                    )
+                    .arg(
+                        Arg::new("namespace")
+                            .short('n')
+                            .long("namespace")
+                            .help("Namespace of the resources")
+                            .value_name("NAMESPACE"),
+                    )
+                    .arg(
+                        Arg::new("selector")
+                            .short('l')
+                            .long("selector")
+                            .help("Label selector, such as app=web")
+                            .value_name("SELECTOR"),
+                    )
+                    .arg(
+                        Arg::new("all-namespaces")
+                            .short('A')
+                            .long("all-namespaces")
+                            .help("List the resources in all namespaces")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("context")
+                            .long("context")
+                            .help("The kubeconfig context to use")
+                            .value_name("CONTEXT"),
+                    )
+                    .arg(
+                        Arg::new("output")
+                            .short('o')
+                            .long("output")
+                            .help("Print YAML (the default) or JSON, like --output-format")
+                            .value_parser(["yaml", "json"])
+                            .value_name("FORMAT"),
+                    ),
+            )
+    }
+
+    fn handle_input(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let peeler = Peeler::with_options(peel_options(matches)?);
+        let report = match matches.get_one::<String>("filename") {
+            Some(file) if file != "-" => peeler.peel_file(file, None)?,
+            _ => peeler.peel_stdin(None)?,
+        };
+        print_report(matches, &report)
+    }
+
+    fn handle_get_command(&self, matches: &ArgMatches) -> Result<(), RspError> {
+        let mut options = peel_options(matches)?;
+        if matches
+            .get_one::<String>("output")
+            .is_some_and(|output| output == "json")
+        {
+            options = options.output_format(DocumentFormat::Json);
+        }
+        let peeler = Peeler::with_options(options);
+
+        let output = process::Command::new(kubectl())
+            .args(kubectl_get_args(matches))
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(|e| RspError::Processing(format!("failed to run kubectl: {e}")))?;
+        if !output.status.success() {
+            return Err(RspError::Processing(format!(
+                "kubectl get exited with {}",
+                output.status
+            )));
+        }
+
+        let report = peeler.peel_reader(output.stdout.as_slice(), io::stdout().lock())?;
+        print_report(matches, &report)
+    }
+}
+
+fn kubectl() -> String {
+    std::env::var(KUBECTL_ENV).unwrap_or_else(|_| "kubectl".to_string())
+}
+
+/// The `kubectl get` command line matching the plugin's arguments.
+fn kubectl_get_args(matches: &ArgMatches) -> Vec<String> {
+    let mut args = vec!["get".to_string()];
+    args.extend(matches.get_one::<String>("resource").cloned());
+    args.extend(
+        matches
+            .get_many::<String>("names")
+            .unwrap_or_default()
+            .cloned(),
+    );
+    for (flag, name) in [
+        ("--namespace", "namespace"),
+        ("--selector", "selector"),
+        ("--context", "context"),
+    ] {
+        if let Some(value) = matches.get_one::<String>(name) {
+            args.push(format!("{flag}={value}"));
+        }
+    }
+    if matches.get_flag("all-namespaces") {
+        args.push("--all-namespaces".to_string());
+    }
+    args.extend(["-o".to_string(), "yaml".to_string()]);
+    args
+}
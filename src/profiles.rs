@@ -0,0 +1,264 @@
+//! Built-in target rules for well-known resources that embed files.
+//!
+//! Each [`Profile`] stands for the [`TargetRule`]s of one tool. All but the
+//! Grafana profile are enabled by default; the others are turned on with
+//! [`PeelOptions::profile`](crate::options::PeelOptions::profile), the
+//! `--profile` flag or `profiles` in `.rsp.toml`.
+
+use crate::error::RspError;
+use crate::rules::TargetRule;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Helm values of Argo CD `Application`s and `ApplicationSet`s.
+    ArgoCd,
+    /// Helm values of Flux `HelmRelease`s and k3s `HelmChart`s.
+    Flux,
+    /// Dashboards of ConfigMaps labeled `grafana_dashboard`, as loaded by
+    /// the Grafana sidecar.
+    Grafana,
+    /// Literals of kustomize `configMapGenerator`s and `secretGenerator`s.
+    Kustomize,
+    /// Inline `configs` and JSON `environment` values of Docker Compose
+    /// files.
+    Compose,
+    /// Step Functions definitions, `UserData` and inline IAM policies of
+    /// CloudFormation and SAM templates.
+    CloudFormation,
+    /// JSON `env` values and multi-line `command` and `args` strings of the
+    /// Pod templates in Deployments, StatefulSets, DaemonSets, Jobs and
+    /// CronJobs.
+    Workloads,
+    /// `script` fields of Tekton Tasks, Pipelines and their runs.
+    Tekton,
+    /// `script.source` fields of Argo Workflows templates.
+    #[serde(rename = "argo-workflows")]
+    ArgoWorkflows,
+}
+
+impl Profile {
+    /// Every profile, in the order their rules are tried.
+    pub const ALL: &'static [Profile] = &[
+        Profile::ArgoCd,
+        Profile::Flux,
+        Profile::Grafana,
+        Profile::Kustomize,
+        Profile::Compose,
+        Profile::CloudFormation,
+        Profile::Workloads,
+        Profile::Tekton,
+        Profile::ArgoWorkflows,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::ArgoCd => "argocd",
+            Profile::Flux => "flux",
+            Profile::Grafana => "grafana",
+            Profile::Kustomize => "kustomize",
+            Profile::Compose => "compose",
+            Profile::CloudFormation => "cloudformation",
+            Profile::Workloads => "workloads",
+            Profile::Tekton => "tekton",
+            Profile::ArgoWorkflows => "argo-workflows",
+        }
+    }
+
+    /// Whether the profile applies unless turned off.
+    pub fn enabled_by_default(self) -> bool {
+        match self {
+            Profile::ArgoCd
+            | Profile::Flux
+            | Profile::Kustomize
+            | Profile::Compose
+            | Profile::CloudFormation
+            | Profile::Workloads
+            | Profile::Tekton
+            | Profile::ArgoWorkflows => true,
+            Profile::Grafana => false,
+        }
+    }
+
+    pub fn rules(self) -> Vec<TargetRule> {
+        match self {
+            Profile::ArgoCd => {
+                let helm_values = |rule: TargetRule, prefix: &str| {
+                    rule.path(&format!("{prefix}.source.helm.values"))
+                        .path(&format!("{prefix}.sources[*].helm.values"))
+                        .format("yaml")
+                        .strings_only(true)
+                };
+                vec![
+                    helm_values(
+                        TargetRule::new()
+                            .api_version("argoproj.io/v1alpha1")
+                            .kind("Application"),
+                        "spec",
+                    ),
+                    helm_values(
+                        TargetRule::new()
+                            .api_version("argoproj.io/v1alpha1")
+                            .kind("ApplicationSet"),
+                        "spec.template.spec",
+                    ),
+                ]
+            }
+            Profile::Flux => vec![
+                TargetRule::new()
+                    .kind("HelmRelease")
+                    .path("spec.values")
+                    .format("yaml")
+                    .strings_only(true),
+                TargetRule::new()
+                    .api_version("helm.cattle.io/v1")
+                    .kind("HelmChart")
+                    .path("spec.valuesContent")
+                    .format("yaml")
+                    .strings_only(true),
+            ],
+            Profile::Grafana => vec![
+                TargetRule::new()
+                    .kind("ConfigMap")
+                    .label("grafana_dashboard", "*")
+                    .path("data")
+                    .extension("json")
+                    .format("grafana")
+                    .reformat(true),
+            ],
+            // `kind` is optional in a kustomization, and components carry
+            // generators too, so any document with generators matches.
+            Profile::Kustomize => vec![
+                TargetRule::new()
+                    .path("configMapGenerator[*].literals[*]")
+                    .path("secretGenerator[*].literals[*]")
+                    .literals(true),
+            ],
+            // Compose files have no `kind`; `services` tells them apart.
+            // Environment values are only peeled when they hold JSON,
+            // which is then pretty-printed.
+            Profile::Compose => vec![
+                TargetRule::new()
+                    .root_key("services")
+                    .path("configs.*.content"),
+                TargetRule::new()
+                    .root_key("services")
+                    .path("services.*.environment")
+                    .reformat(true)
+                    .detect_content(true),
+                TargetRule::new()
+                    .root_key("services")
+                    .path("services.*.environment[*]")
+                    .literals(true)
+                    .reformat(true)
+                    .detect_content(true),
+            ],
+            // Templates have no `kind`; `Resources` tells them apart.
+            // Properties written as YAML mappings are left alone, and
+            // strings under tags such as `!Sub` are peeled in place.
+            Profile::CloudFormation => vec![
+                TargetRule::new()
+                    .root_key("Resources")
+                    .path("Resources.*.Properties.DefinitionString")
+                    .path("Resources.*.Properties.PolicyDocument")
+                    .path("Resources.*.Properties.AssumeRolePolicyDocument")
+                    .path("Resources.*.Properties.Policies[*].PolicyDocument")
+                    .path("Resources.*.Properties.UserData")
+                    .path(r#"Resources.*.Properties.UserData["Fn::Base64"]"#)
+                    .path(r#"Resources.*.Properties.LaunchTemplateData.UserData["Fn::Base64"]"#)
+                    .strings_only(true)
+                    .reformat(true)
+                    .detect_content(true),
+            ],
+            // Values are taken as stored, so single-line commands and
+            // regexes keep their backslashes; JSON that parses as stored
+            // is pretty-printed.
+            Profile::Workloads => {
+                let pod_template = |kind: &str, pod_spec: &str| {
+                    let mut rule = TargetRule::new().kind(kind);
+                    for containers in ["initContainers", "containers"] {
+                        let prefix = format!("{pod_spec}.{containers}[*]");
+                        rule = rule
+                            .path(&format!("{prefix}.env[*].value"))
+                            .path(&format!("{prefix}.command[*]"))
+                            .path(&format!("{prefix}.args[*]"));
+                    }
+                    rule.multi_line_only(true)
+                        .detect_content(true)
+                        .reformat(true)
+                };
+                vec![
+                    pod_template("Deployment", "spec.template.spec"),
+                    pod_template("StatefulSet", "spec.template.spec"),
+                    pod_template("DaemonSet", "spec.template.spec"),
+                    pod_template("Job", "spec.template.spec"),
+                    pod_template("CronJob", "spec.jobTemplate.spec.template.spec"),
+                ]
+            }
+            // Scripts are text, so a shebang line stays first; one-liners
+            // are left alone.
+            Profile::Tekton => {
+                let task_spec = |rule: TargetRule, prefix: &str| {
+                    rule.path(&format!("{prefix}.steps[*].script"))
+                        .path(&format!("{prefix}.sidecars[*].script"))
+                };
+                let pipeline_spec = |rule: TargetRule, prefix: &str| {
+                    let rule = task_spec(rule, &format!("{prefix}.tasks[*].taskSpec"));
+                    task_spec(rule, &format!("{prefix}.finally[*].taskSpec"))
+                };
+                ["tekton.dev/v1", "tekton.dev/v1beta1"]
+                    .into_iter()
+                    .flat_map(|api_version| {
+                        let rule =
+                            |kind: &str| TargetRule::new().api_version(api_version).kind(kind);
+                        [
+                            task_spec(rule("Task"), "spec"),
+                            task_spec(rule("ClusterTask"), "spec"),
+                            task_spec(rule("TaskRun"), "spec.taskSpec"),
+                            pipeline_spec(rule("Pipeline"), "spec"),
+                            pipeline_spec(rule("PipelineRun"), "spec.pipelineSpec"),
+                        ]
+                    })
+                    .map(|rule| rule.strings_only(true).multi_line_only(true))
+                    .collect()
+            }
+            Profile::ArgoWorkflows => {
+                let workflow = |kind: &str, prefix: &str| {
+                    TargetRule::new()
+                        .api_version("argoproj.io/v1alpha1")
+                        .kind(kind)
+                        .path(&format!("{prefix}.templates[*].script.source"))
+                        .strings_only(true)
+                        .multi_line_only(true)
+                };
+                vec![
+                    workflow("Workflow", "spec"),
+                    workflow("WorkflowTemplate", "spec"),
+                    workflow("ClusterWorkflowTemplate", "spec"),
+                    workflow("CronWorkflow", "spec.workflowSpec"),
+                ]
+            }
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Profile {
+    type Err = RspError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Profile::ALL
+            .iter()
+            .copied()
+            .find(|profile| profile.name() == name)
+            .ok_or_else(|| RspError::InvalidFormat(format!("unknown profile '{name}'")))
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Serialize;
+use std::fmt;
+
+/// What a call to [`Peeler::peel_str`](crate::peeler::Peeler::peel_str)
+/// changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PeelReport {
+    pub entries: Vec<ReportEntry>,
+}
+
+/// One embedded file that was looked at while peeling.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportEntry {
+    /// Index of the document in a multi-document stream.
+    pub document: usize,
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    /// The owning resource as `Kind/namespace/name`.
+    pub resource: String,
+    /// Where the value is in its document, in selector syntax, such as
+    /// `data["config.json"]`.
+    pub path: String,
+    pub key: String,
+    /// The format detected from the key, such as `json`.
+    pub format: Option<String>,
+    #[serde(flatten)]
+    pub status: EntryStatus,
+    /// Length of the value as found in the manifest.
+    pub bytes_before: usize,
+    /// Length of the value once peeled; equal to `bytes_before` unless the
+    /// entry was peeled.
+    pub bytes_after: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum EntryStatus {
+    Peeled,
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+/// The result of peeling a manifest: the rendered text and a report of what
+/// was peeled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeelOutput {
+    pub text: String,
+    pub report: PeelReport,
+}
+
+impl PeelReport {
+    /// Entries that were peeled.
+    pub fn peeled(&self) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status == EntryStatus::Peeled)
+    }
+
+    /// Entries that were left alone on purpose.
+    pub fn skipped(&self) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, EntryStatus::Skipped { .. }))
+    }
+
+    /// Entries that could not be peeled and were left unchanged.
+    pub fn failed(&self) -> impl Iterator<Item = &ReportEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, EntryStatus::Failed { .. }))
+    }
+}
+
+/// A human-readable summary, one line per entry and warning.
+impl fmt::Display for PeelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let location = if entry.resource.is_empty() {
+                format!("document {} {}", entry.document, entry.path)
+            } else {
+                format!("{} {}", entry.resource, entry.path)
+            };
+            match &entry.status {
+                EntryStatus::Peeled => writeln!(
+                    f,
+                    "peeled   {location} ({}, {} -> {} bytes)",
+                    entry.format.as_deref().unwrap_or("text"),
+                    entry.bytes_before,
+                    entry.bytes_after
+                )?,
+                EntryStatus::Skipped { reason } => writeln!(f, "skipped  {location}: {reason}")?,
+                EntryStatus::Failed { error } => writeln!(f, "failed   {location}: {error}")?,
+            }
+            for warning in &entry.warnings {
+                writeln!(f, "warning  {location}: {warning}")?;
+            }
+        }
+        writeln!(
+            f,
+            "{} peeled, {} skipped, {} failed",
+            self.peeled().count(),
+            self.skipped().count(),
+            self.failed().count()
+        )
+    }
+}
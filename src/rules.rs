@@ -0,0 +1,282 @@
+//! Which resources are peeled, and where their embedded files live.
+//!
+//! A [`TargetRule`] matches documents by `apiVersion`, `kind`, name, labels
+//! and top-level keys, and lists [selectors](crate::selector) for the nodes
+//! inside them holding embedded files. A selected mapping makes each of its
+//! string entries an embedded file, like a ConfigMap's `data`; a selected
+//! string is one embedded file named after the last key on its path, or,
+//! for rules reading [literals](TargetRule::literals), after the key of its
+//! `KEY=VALUE` pair.
+//!
+//! Rules are tried in order, and each value is claimed by the first matching
+//! rule that reaches it.
+
+use crate::error::RspError;
+use crate::kustomize::Literal;
+use crate::manifest;
+use crate::selector::{self, PathSegment, Selector};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TargetRule {
+    #[serde(default)]
+    pub(crate) api_version: Option<String>,
+    #[serde(default)]
+    pub(crate) kind: Option<String>,
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    #[serde(default)]
+    pub(crate) labels: BTreeMap<String, String>,
+    /// Top-level keys documents must have, for files that have no `kind`.
+    #[serde(default)]
+    pub(crate) root_keys: Vec<String>,
+    pub(crate) paths: Vec<String>,
+    /// Extensions a file's key must have, if any are listed.
+    #[serde(default)]
+    pub(crate) extensions: Vec<String>,
+    /// Name of the format every file under the paths is read as, instead
+    /// of detecting it from the key.
+    #[serde(default)]
+    pub(crate) format: Option<String>,
+    /// Whether files are rewritten in their format's canonical layout,
+    /// such as pretty-printed JSON.
+    #[serde(default)]
+    pub(crate) reformat: bool,
+    /// Whether selected mappings are ignored, so only strings are files.
+    #[serde(default)]
+    pub(crate) strings_only: bool,
+    /// Whether selected strings are kustomize `KEY=VALUE` literals, so
+    /// only the value is the file.
+    #[serde(default)]
+    pub(crate) literals: bool,
+    /// Whether selected strings are taken as stored, and only peeled when
+    /// they span several lines or parse in their detected format.
+    #[serde(default)]
+    pub(crate) multi_line_only: bool,
+    /// Whether files no format claims by key are recognized by content.
+    #[serde(default)]
+    pub(crate) detect_content: bool,
+}
+
+impl TargetRule {
+    /// A rule matching every document and listing no path yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in rule: the `data` of every ConfigMap.
+    pub fn configmap_data() -> Self {
+        Self::new().kind("ConfigMap").path("data")
+    }
+
+    pub fn api_version(mut self, api_version: &str) -> Self {
+        self.api_version = Some(api_version.to_string());
+        self
+    }
+
+    pub fn kind(mut self, kind: &str) -> Self {
+        self.kind = Some(kind.to_string());
+        self
+    }
+
+    /// Only matches resources with this `metadata.name`.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Only matches resources carrying this label, with any value when
+    /// `value` is `*`. Can be called several times; all labels must match.
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Only matches documents with this top-level key, such as `services`
+    /// for Compose files. Can be called several times; all keys must be
+    /// present.
+    pub fn root_key(mut self, key: &str) -> Self {
+        self.root_keys.push(key.to_string());
+        self
+    }
+
+    /// Adds a path holding embedded files, such as `data` or
+    /// `spec.values`, in [selector](crate::selector) syntax.
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_string());
+        self
+    }
+
+    /// Only takes files whose key has this extension, such as `json`, and
+    /// leaves the others to later rules. Can be called several times; any
+    /// listed extension matches.
+    pub fn extension(mut self, extension: &str) -> Self {
+        self.extensions.push(extension.to_string());
+        self
+    }
+
+    /// Reads every file under this rule's paths as the named format.
+    pub fn format(mut self, format: &str) -> Self {
+        self.format = Some(format.to_string());
+        self
+    }
+
+    /// Rewrites every file under this rule's paths in its format's
+    /// canonical layout instead of only unescaping it, which makes
+    /// single-line JSON readable.
+    pub fn reformat(mut self, enabled: bool) -> Self {
+        self.reformat = enabled;
+        self
+    }
+
+    /// Only takes selected strings as embedded files, ignoring selected
+    /// mappings. Useful for fields such as Helm values that hold either
+    /// a YAML string or the values themselves.
+    pub fn strings_only(mut self, enabled: bool) -> Self {
+        self.strings_only = enabled;
+        self
+    }
+
+    /// Reads selected strings as kustomize `KEY=VALUE` literals: the file
+    /// is named after `KEY`, and only `VALUE` is peeled. Strings without
+    /// a key are ignored.
+    pub fn literals(mut self, enabled: bool) -> Self {
+        self.literals = enabled;
+        self
+    }
+
+    /// Takes selected strings as stored: only strings that already span
+    /// several lines, scripts escaped onto one line after a shebang, and
+    /// strings a detected format such as JSON parses as they are, are
+    /// peeled, and none is unescaped otherwise. Useful for fields such as
+    /// container `args`, where a backslash is rarely an escape.
+    pub fn multi_line_only(mut self, enabled: bool) -> Self {
+        self.multi_line_only = enabled;
+        self
+    }
+
+    /// Recognizes files whose key names no format by their content, such
+    /// as a JSON object in an environment variable. Off by default, so a
+    /// ConfigMap key without an extension is never read as JSON.
+    pub fn detect_content(mut self, enabled: bool) -> Self {
+        self.detect_content = enabled;
+        self
+    }
+
+    fn claims_key(&self, key: &str) -> bool {
+        self.extensions.is_empty()
+            || Path::new(key)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| self.extensions.iter().any(|wanted| wanted == extension))
+    }
+
+    pub fn matches(&self, document: &Value) -> bool {
+        let field = |value: Option<&str>, wanted: &Option<String>| {
+            wanted.as_deref().is_none_or(|wanted| value == Some(wanted))
+        };
+
+        field(
+            document.get("apiVersion").and_then(Value::as_str),
+            &self.api_version,
+        ) && field(manifest::kind(document), &self.kind)
+            && field(manifest::name(document), &self.name)
+            && self.labels.iter().all(|(key, wanted)| {
+                document
+                    .get("metadata")
+                    .and_then(|metadata| metadata.get("labels"))
+                    .and_then(|labels| labels.get(key.as_str()))
+                    .and_then(Value::as_str)
+                    .is_some_and(|value| wanted == "*" || value == wanted)
+            })
+            && self
+                .root_keys
+                .iter()
+                .all(|key| document.get(key.as_str()).is_some())
+    }
+}
+
+/// A place in a document holding one embedded file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Slot {
+    /// The concrete path from the document root to the value.
+    pub path: Vec<PathSegment>,
+    /// The file's name: its mapping key, or the last key on its path.
+    pub key: String,
+    /// The format forced by the rule, if any.
+    pub format: Option<String>,
+    /// Whether the rule selected the string itself rather than a mapping
+    /// of files, so it is peeled even when its format is unknown.
+    pub explicit: bool,
+    /// Whether the file is rewritten in its format's canonical layout.
+    pub reformat: bool,
+    /// Whether the value is a `KEY=VALUE` literal holding the file.
+    pub literal: bool,
+    /// Whether the string is taken as stored, and only peeled when it
+    /// spans several lines or parses in its detected format.
+    pub multi_line_only: bool,
+    /// Whether the format may be detected from the content.
+    pub detect_content: bool,
+}
+
+/// Lists the embedded file slots `rules` claim in `document`, in rule order.
+/// Entries that are not strings are included so they can be reported.
+pub(crate) fn slots(rules: &[TargetRule], document: &Value) -> Result<Vec<Slot>, RspError> {
+    let mut slots: Vec<Slot> = Vec::new();
+
+    for rule in rules.iter().filter(|rule| rule.matches(document)) {
+        for rule_path in &rule.paths {
+            let selector = Selector::parse(rule_path)?;
+            for path in selector.select(document) {
+                let found = match selector::lookup(document, &path) {
+                    Some(Value::Mapping(map)) if !rule.strings_only => map
+                        .keys()
+                        .filter_map(Value::as_str)
+                        .map(|key| {
+                            let mut entry_path = path.clone();
+                            entry_path.push(PathSegment::Key(key.to_string()));
+                            (entry_path, key.to_string(), false)
+                        })
+                        .collect(),
+                    Some(Value::String(literal)) if rule.literals => Literal::parse(literal)
+                        .map(|literal| (path, literal.key, false))
+                        .into_iter()
+                        .collect(),
+                    Some(Value::String(_)) => {
+                        let key = path
+                            .iter()
+                            .rev()
+                            .find_map(|segment| match segment {
+                                PathSegment::Key(key) => Some(key.clone()),
+                                PathSegment::Index(_) => None,
+                            })
+                            .unwrap_or_default();
+                        vec![(path, key, true)]
+                    }
+                    _ => Vec::new(),
+                };
+
+                for (path, key, explicit) in found {
+                    if !rule.claims_key(&key) || slots.iter().any(|slot| slot.path == path) {
+                        continue;
+                    }
+                    slots.push(Slot {
+                        path,
+                        key,
+                        format: rule.format.clone(),
+                        explicit,
+                        reformat: rule.reformat,
+                        literal: rule.literals,
+                        multi_line_only: rule.multi_line_only,
+                        detect_content: rule.detect_content,
+                    });
+                }
+            }
+        }
+    }
+    Ok(slots)
+}
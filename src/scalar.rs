@@ -0,0 +1,185 @@
+use serde_yaml::Value;
+
+/// Whether `content` can be written as a literal block scalar without
+/// losing characters YAML would normalize or reject.
+pub fn is_block_compatible(content: &str) -> bool {
+    !content
+        .chars()
+        .any(|c| (c.is_control() && c != '\n' && c != '\t') || c == '\u{feff}')
+}
+
+/// Whether `s` reads back unchanged when written as a plain scalar, also by
+/// YAML 1.1 parsers such as the ones kubectl and the AWS CLI use.
+pub fn is_plain_safe(s: &str) -> bool {
+    !s.is_empty()
+        && s.trim() == s
+        && !s.contains(['\n', '\r', '\t'])
+        && !is_yaml11_non_string(s)
+        && matches!(serde_yaml::from_str::<Value>(s), Ok(Value::String(parsed)) if parsed == s)
+}
+
+/// Plain scalars YAML 1.2 reads as strings but YAML 1.1 does not: the
+/// booleans `yes`, `no`, `on`, `off`, `y` and `n`, and dates such as
+/// CloudFormation's `2010-09-09`.
+fn is_yaml11_non_string(s: &str) -> bool {
+    const BOOLEANS: &[&str] = &[
+        "y", "Y", "yes", "Yes", "YES", "n", "N", "no", "No", "NO", "on", "On", "ON", "off", "Off",
+        "OFF",
+    ];
+    let bytes = s.as_bytes();
+    let is_date = bytes.len() >= 10
+        && bytes[..4].iter().all(u8::is_ascii_digit)
+        && bytes[4] == b'-'
+        && bytes[5..7].iter().all(u8::is_ascii_digit)
+        && bytes[7] == b'-'
+        && bytes[8..10].iter().all(u8::is_ascii_digit)
+        && (bytes.len() == 10 || matches!(bytes[10], b'T' | b't' | b' '));
+    BOOLEANS.contains(&s) || is_date
+}
+
+/// Renders `content` as a literal block scalar whose lines are indented to
+/// `content_indent` columns under a parent at `parent_indent` columns.
+///
+/// The chomping and indentation indicators are chosen so the scalar reads
+/// back exactly. The result has no trailing line break.
+pub fn block_scalar(content: &str, parent_indent: usize, content_indent: usize) -> String {
+    let (mut output, body) = block_header('|', content, parent_indent, content_indent);
+    for line in body.split('\n') {
+        push_block_line(&mut output, line, content_indent);
+    }
+    output
+}
+
+/// Renders `content` as a folded block scalar laid out like
+/// [`block_scalar`], with long lines wrapped at single spaces.
+///
+/// Folding reads a line break between two lines of text as a space, so an
+/// empty line is added wherever `content` has a real line break there.
+pub fn folded_block_scalar(content: &str, parent_indent: usize, content_indent: usize) -> String {
+    let (mut output, body) = block_header('>', content, parent_indent, content_indent);
+    let folds = |line: &str| !line.is_empty() && !line.starts_with([' ', '\t']);
+    let width = FOLD_WIDTH.saturating_sub(content_indent);
+    let mut previous_text: Option<&str> = None;
+    for line in body.split('\n') {
+        if line.is_empty() {
+            push_block_line(&mut output, line, content_indent);
+            continue;
+        }
+        if previous_text.is_some_and(folds) && folds(line) {
+            output.push('\n');
+        }
+        previous_text = Some(line);
+        if folds(line) {
+            for piece in fold_line(line, width) {
+                push_block_line(&mut output, piece, content_indent);
+            }
+        } else {
+            push_block_line(&mut output, line, content_indent);
+        }
+    }
+    output
+}
+
+/// Column past which [`folded_block_scalar`] wraps lines.
+const FOLD_WIDTH: usize = 80;
+
+/// Returns the block scalar header for `indicator` and the content lines
+/// left once the chomping indicator accounts for the final line breaks.
+fn block_header(
+    indicator: char,
+    content: &str,
+    parent_indent: usize,
+    content_indent: usize,
+) -> (String, &str) {
+    let mut output = String::from(indicator);
+    if content
+        .split('\n')
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.starts_with(' '))
+    {
+        output.push_str(&(content_indent - parent_indent).to_string());
+    }
+    let body = match content.strip_suffix('\n') {
+        Some(body) => {
+            if body.ends_with('\n') || body.is_empty() {
+                output.push('+');
+            }
+            body
+        }
+        None => {
+            output.push('-');
+            content
+        }
+    };
+    (output, body)
+}
+
+fn push_block_line(output: &mut String, line: &str, content_indent: usize) {
+    output.push('\n');
+    if !line.is_empty() {
+        output.push_str(&" ".repeat(content_indent));
+        output.push_str(line);
+    }
+}
+
+/// Splits `line` at single spaces between other characters into pieces of
+/// at most `width` bytes where possible.
+fn fold_line(line: &str, width: usize) -> Vec<&str> {
+    let is_break = |index: usize| {
+        !line[..index].ends_with(char::is_whitespace)
+            && line[index + 1..].starts_with(|c: char| !c.is_whitespace())
+    };
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut candidate = None;
+    let breaks = line
+        .match_indices(' ')
+        .map(|(index, _)| index)
+        .filter(|&index| index > 0 && is_break(index));
+    for index in breaks.chain(std::iter::once(line.len())) {
+        if index - start > width
+            && let Some(at) = candidate.take()
+        {
+            pieces.push(&line[start..at]);
+            start = at + 1;
+        }
+        if index < line.len() {
+            candidate = Some(index);
+        }
+    }
+    pieces.push(&line[start..]);
+    pieces
+}
+
+/// Renders `s` as a double-quoted scalar on a single line.
+pub fn double_quoted(s: &str) -> String {
+    let mut output = String::from("\"");
+    for ch in s.chars() {
+        match ch {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\t' => output.push_str("\\t"),
+            '\r' => output.push_str("\\r"),
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// Renders `s` as a single-quoted scalar on a single line.
+pub fn single_quoted(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Renders `s` as a plain scalar when that is safe, and double-quoted
+/// otherwise.
+pub fn flow_scalar(s: &str) -> String {
+    if is_plain_safe(s) {
+        s.to_string()
+    } else {
+        double_quoted(s)
+    }
+}
@@ -0,0 +1,391 @@
+//! JSONPath/yq-style selectors for nodes inside YAML documents.
+//!
+//! A selector is a chain of steps, each narrowing down the nodes reached so
+//! far:
+//!
+//! - `.key` or `["key"]` follows a mapping key (the leading `.` and a `$`
+//!   root are optional, so `data` and `$.data` equal `.data`);
+//! - `[2]` takes a sequence item;
+//! - `[*]` or `.*` takes every item of a sequence or value of a mapping;
+//! - `[?(@.name=="CONFIG")]` keeps the items whose field equals (or with
+//!   `!=`, differs from) a literal.
+//!
+//! Tagged nodes, such as CloudFormation's `!Sub "..."`, are treated as the
+//! node under the tag.
+//!
+//! ```
+//! use rsp_cli::selector::Selector;
+//!
+//! let selector: Selector = r#".spec.containers[*].env[?(@.name=="CONFIG")].value"#
+//!     .parse()
+//!     .unwrap();
+//! ```
+
+use crate::error::RspError;
+use serde_yaml::Value;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    Wildcard,
+    Filter {
+        field: Vec<String>,
+        negated: bool,
+        literal: Value,
+    },
+}
+
+/// One step of a concrete path to a node.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Self, RspError> {
+        Parser::new(selector).parse()
+    }
+
+    /// Returns the concrete path of every node the selector reaches in
+    /// `document`, in document order.
+    pub fn select(&self, document: &Value) -> Vec<Vec<PathSegment>> {
+        let mut reached = vec![(Vec::new(), document)];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for (path, value) in reached {
+                for (segment, child) in step.apply(value) {
+                    let mut child_path = path.clone();
+                    child_path.push(segment);
+                    next.push((child_path, child));
+                }
+            }
+            reached = next;
+        }
+        reached.into_iter().map(|(path, _)| path).collect()
+    }
+}
+
+impl FromStr for Selector {
+    type Err = RspError;
+
+    fn from_str(selector: &str) -> Result<Self, Self::Err> {
+        Self::parse(selector)
+    }
+}
+
+impl Step {
+    fn apply<'a>(&self, value: &'a Value) -> Vec<(PathSegment, &'a Value)> {
+        match self {
+            Step::Key(key) => value
+                .as_mapping()
+                .and_then(|map| map.get(key.as_str()))
+                .map(|child| (PathSegment::Key(key.clone()), child))
+                .into_iter()
+                .collect(),
+            Step::Index(index) => value
+                .as_sequence()
+                .and_then(|items| items.get(*index))
+                .map(|child| (PathSegment::Index(*index), child))
+                .into_iter()
+                .collect(),
+            Step::Wildcard => children(value),
+            Step::Filter {
+                field,
+                negated,
+                literal,
+            } => children(value)
+                .into_iter()
+                .filter(|(_, child)| {
+                    let found = field
+                        .iter()
+                        .try_fold(*child, |value, key| value.get(key.as_str()))
+                        .map(untagged);
+                    (found == Some(literal)) != *negated
+                })
+                .collect(),
+        }
+    }
+}
+
+fn children(value: &Value) -> Vec<(PathSegment, &Value)> {
+    match untagged(value) {
+        Value::Sequence(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (PathSegment::Index(index), item))
+            .collect(),
+        Value::Mapping(map) => map
+            .iter()
+            .filter_map(|(key, child)| Some((PathSegment::Key(key.as_str()?.to_string()), child)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Follows a concrete path from `document`. Tags such as CloudFormation's
+/// `!Sub` are looked through, so the node returned is never tagged and
+/// changing it keeps the tag.
+pub fn lookup<'a>(document: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(document, |value, segment| match segment {
+            PathSegment::Key(key) => value.get(key.as_str()),
+            PathSegment::Index(index) => value.get(index),
+        })
+        .map(untagged)
+}
+
+/// Follows a concrete path from `document`, mutably.
+pub fn lookup_mut<'a>(document: &'a mut Value, path: &[PathSegment]) -> Option<&'a mut Value> {
+    path.iter()
+        .try_fold(document, |value, segment| match segment {
+            PathSegment::Key(key) => value.get_mut(key.as_str()),
+            PathSegment::Index(index) => value.get_mut(index),
+        })
+        .map(untagged_mut)
+}
+
+/// The node under any tags on `value`.
+fn untagged(mut value: &Value) -> &Value {
+    while let Value::Tagged(tagged) = value {
+        value = &tagged.value;
+    }
+    value
+}
+
+fn untagged_mut(mut value: &mut Value) -> &mut Value {
+    while let Value::Tagged(tagged) = value {
+        value = &mut tagged.value;
+    }
+    value
+}
+
+/// Renders a concrete path in selector syntax, without the leading `.`,
+/// such as `spec.containers[0].env`.
+pub fn display_path(path: &[PathSegment]) -> String {
+    let mut output = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) if is_identifier(key) => {
+                if !output.is_empty() {
+                    output.push('.');
+                }
+                output.push_str(key);
+            }
+            PathSegment::Key(key) => {
+                output.push_str(&format!("[{}]", serde_json::Value::String(key.clone())));
+            }
+            PathSegment::Index(index) => output.push_str(&format!("[{index}]")),
+        }
+    }
+    output
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    fn parse(mut self) -> Result<Selector, RspError> {
+        let mut steps = Vec::new();
+        self.eat('$');
+        if self.rest().is_empty() || self.rest() == "." {
+            return Err(self.error("expected a key, index or filter"));
+        }
+        if !self.rest().starts_with(['.', '[']) {
+            steps.push(self.identifier_step()?);
+        }
+
+        while let Some(next) = self.peek() {
+            match next {
+                '.' => {
+                    self.position += 1;
+                    steps.push(self.identifier_step()?);
+                }
+                '[' => {
+                    self.position += 1;
+                    steps.push(self.bracket_step()?);
+                }
+                _ => return Err(self.error("expected '.' or '['")),
+            }
+        }
+        Ok(Selector { steps })
+    }
+
+    fn identifier_step(&mut self) -> Result<Step, RspError> {
+        if self.eat('*') {
+            return Ok(Step::Wildcard);
+        }
+        let key = self.identifier();
+        if key.is_empty() {
+            return Err(self.error("expected a key"));
+        }
+        Ok(Step::Key(key))
+    }
+
+    fn bracket_step(&mut self) -> Result<Step, RspError> {
+        let step = match self.peek() {
+            Some('*') => {
+                self.position += 1;
+                Step::Wildcard
+            }
+            Some('"' | '\'') => Step::Key(self.quoted()?),
+            Some('?') => {
+                self.position += 1;
+                self.expect('(')?;
+                self.expect('@')?;
+                let mut field = Vec::new();
+                while self.eat('.') {
+                    let key = self.identifier();
+                    if key.is_empty() {
+                        return Err(self.error("expected a field name"));
+                    }
+                    field.push(key);
+                }
+                self.skip_spaces();
+                let negated = if self.rest().starts_with("==") {
+                    false
+                } else if self.rest().starts_with("!=") {
+                    true
+                } else {
+                    return Err(self.error("expected '==' or '!='"));
+                };
+                self.position += 2;
+                self.skip_spaces();
+                let literal = self.literal()?;
+                self.skip_spaces();
+                self.expect(')')?;
+                Step::Filter {
+                    field,
+                    negated,
+                    literal,
+                }
+            }
+            _ => {
+                let digits: String = self
+                    .rest()
+                    .chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect();
+                if digits.is_empty() {
+                    return Err(self.error("expected an index, '*', a quoted key or a filter"));
+                }
+                self.position += digits.len();
+                Step::Index(
+                    digits
+                        .parse()
+                        .map_err(|_| self.error("index out of range"))?,
+                )
+            }
+        };
+        self.expect(']')?;
+        Ok(step)
+    }
+
+    fn identifier(&mut self) -> String {
+        let key: String = self
+            .rest()
+            .chars()
+            .take_while(|c| !matches!(c, '.' | '[' | ']' | '(' | ')' | '=' | '!' | ' '))
+            .collect();
+        self.position += key.len();
+        key
+    }
+
+    fn quoted(&mut self) -> Result<String, RspError> {
+        let quote = self.peek().expect("called on a quote");
+        self.position += 1;
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((offset, ch)) = chars.next() {
+            match ch {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                c if c == quote => {
+                    self.position += offset + 1;
+                    return Ok(value);
+                }
+                c => value.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// A quoted string, or a bare number, boolean or null.
+    fn literal(&mut self) -> Result<Value, RspError> {
+        if matches!(self.peek(), Some('"' | '\'')) {
+            return Ok(Value::String(self.quoted()?));
+        }
+        let bare: String = self
+            .rest()
+            .chars()
+            .take_while(|c| !matches!(c, ')' | ' '))
+            .collect();
+        if bare.is_empty() {
+            return Err(self.error("expected a value to compare with"));
+        }
+        self.position += bare.len();
+        match serde_yaml::from_str(&bare) {
+            Ok(value @ (Value::Number(_) | Value::Bool(_) | Value::Null)) => Ok(value),
+            _ => Err(self.error("strings in filters must be quoted")),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), RspError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{expected}'")))
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.eat(' ') {}
+    }
+
+    fn error(&self, message: &str) -> RspError {
+        RspError::InvalidFormat(format!(
+            "invalid path '{}' at position {}: {message}",
+            self.input, self.position
+        ))
+    }
+}
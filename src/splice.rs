@@ -0,0 +1,473 @@
+//! Text-level editing of scalar values in YAML manifests.
+//!
+//! `serde_yaml` drops comments, key order quirks and quoting styles, so
+//! writing a value back through it rewrites the whole file. The helpers here
+//! instead locate the exact byte span of one scalar in the original text and
+//! replace only that span, rendered in the style the scalar already had.
+
+use crate::scalar;
+use crate::selector::PathSegment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    Literal,
+    Folded,
+}
+
+/// A mapping entry or sequence entry on lines of its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrySpan {
+    /// Byte offset of the start of the entry's first line.
+    pub start: usize,
+    /// Byte offset just past the line break ending its last line.
+    pub end: usize,
+    /// Column of the entry's key or `-`.
+    pub indent: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalarSpan {
+    /// Byte offset of the first character of the value.
+    pub start: usize,
+    /// Byte offset just past the value, excluding the final line break.
+    pub end: usize,
+    pub style: ScalarStyle,
+    /// Column of the key that owns the value.
+    pub key_indent: usize,
+    /// Column of the content lines of a block scalar.
+    pub content_indent: Option<usize>,
+}
+
+struct Line<'a> {
+    start: usize,
+    text: &'a str,
+    indent: usize,
+}
+
+impl Line<'_> {
+    fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    fn is_significant(&self) -> bool {
+        let trimmed = self.text.trim();
+        !trimmed.is_empty() && !trimmed.starts_with('#')
+    }
+}
+
+/// Finds the scalar reached by following `path` through block mappings and
+/// block sequences inside the `document`-th non-empty document of
+/// `content`.
+pub fn locate(content: &str, document: usize, path: &[PathSegment]) -> Option<ScalarSpan> {
+    let lines = split_lines(content);
+    let found = find(&lines, document, path)?;
+    scalar_span(
+        &lines,
+        found.index,
+        found.value_column,
+        found.indent,
+        found.to,
+    )
+}
+
+/// Finds the lines holding the mapping entry or sequence entry at `path`,
+/// value included, the way [`locate`] finds a scalar. Entries sharing
+/// their first line with another node, such as the first key of a
+/// sequence entry, are not found.
+pub fn locate_entry(content: &str, document: usize, path: &[PathSegment]) -> Option<EntrySpan> {
+    let lines = split_lines(content);
+    let found = find(&lines, document, path)?;
+    let line = &lines[found.index];
+    if line.indent != found.indent {
+        return None;
+    }
+
+    let rest = line.text[found.value_column..].trim_start();
+    let compact_sequence =
+        matches!(path.last()?, PathSegment::Key(_)) && (rest.is_empty() || rest.starts_with('#'));
+    let last = (found.index + 1..found.to)
+        .take_while(|&i| {
+            let next = &lines[i];
+            !next.is_significant()
+                || next.indent > found.indent
+                || compact_sequence
+                    && next.indent == found.indent
+                    && is_sequence_entry(&next.text[found.indent..])
+        })
+        .filter(|&i| {
+            lines[i].is_significant() || !lines[i].is_blank() && lines[i].indent > found.indent
+        })
+        .last()
+        .unwrap_or(found.index);
+    Some(EntrySpan {
+        start: line.start,
+        end: lines.get(last + 1).map_or(content.len(), |next| next.start),
+        indent: found.indent,
+    })
+}
+
+/// The node `path` leads to: the line its key or `-` is on, where its value
+/// starts, and the end of the node owning it.
+struct Found {
+    index: usize,
+    value_column: usize,
+    indent: usize,
+    to: usize,
+}
+
+fn find(lines: &[Line<'_>], document: usize, path: &[PathSegment]) -> Option<Found> {
+    let (from, to) = document_range(lines, document)?;
+    let mut node = Node {
+        from,
+        to,
+        column: None,
+    };
+    let mut parent_indent: Option<usize> = None;
+    // A sequence may sit at the same indentation as the key owning it.
+    let mut compact_sequence = false;
+
+    for (depth, segment) in path.iter().enumerate() {
+        let indent = (node.from..node.to)
+            .find(|&index| lines[index].is_significant())
+            .map(|index| node.indent(lines, index))
+            .filter(|indent| {
+                parent_indent.is_none_or(|parent| {
+                    *indent > parent || (compact_sequence && *indent == parent)
+                })
+            })?;
+        let at_indent = |index: usize| {
+            let line = &lines[index];
+            (line.is_significant() && node.indent(lines, index) == indent)
+                .then(|| &line.text[indent..])
+        };
+
+        let (index, value_column) = match segment {
+            PathSegment::Key(key) => {
+                (node.from..node.to).find_map(|index| match parse_key(at_indent(index)?) {
+                    Some((parsed, offset)) if parsed == *key => Some((index, indent + offset)),
+                    _ => None,
+                })?
+            }
+            PathSegment::Index(position) => {
+                let entries = (node.from..node.to).filter_map(|index| {
+                    at_indent(index).map(|text| (index, is_sequence_entry(text)))
+                });
+                if !entries.clone().next()?.1 {
+                    return None;
+                }
+                let (index, _) = entries.filter(|(_, entry)| *entry).nth(*position)?;
+                (index, indent + 1)
+            }
+        };
+
+        if depth + 1 == path.len() {
+            return Some(Found {
+                index,
+                value_column,
+                indent,
+                to: node.to,
+            });
+        }
+
+        let after = &lines[index].text[value_column..];
+        let rest = after.trim_start();
+        let next_is_index = matches!(path[depth + 1], PathSegment::Index(_));
+        let nested_to = (index + 1..node.to)
+            .find(|&i| {
+                let line = &lines[i];
+                line.is_significant()
+                    && (line.indent < indent
+                        || line.indent == indent
+                            && !(next_is_index
+                                && matches!(segment, PathSegment::Key(_))
+                                && is_sequence_entry(&line.text[indent..])))
+            })
+            .unwrap_or(node.to);
+        node = if rest.is_empty() || rest.starts_with('#') {
+            Node {
+                from: index + 1,
+                to: nested_to,
+                column: None,
+            }
+        } else if matches!(segment, PathSegment::Index(_)) {
+            // The entry's content starts on the line of its `-`.
+            Node {
+                from: index,
+                to: nested_to,
+                column: Some(value_column + after.len() - rest.len()),
+            }
+        } else {
+            return None;
+        };
+        compact_sequence = next_is_index && matches!(segment, PathSegment::Key(_));
+        parent_indent = Some(indent);
+    }
+
+    None
+}
+
+/// A range of lines holding one node, whose first line may start at a
+/// column past its indentation, after a sequence entry's `-`.
+struct Node {
+    from: usize,
+    to: usize,
+    column: Option<usize>,
+}
+
+impl Node {
+    fn indent(&self, lines: &[Line<'_>], index: usize) -> usize {
+        match self.column {
+            Some(column) if index == self.from => column,
+            _ => lines[index].indent,
+        }
+    }
+}
+
+fn is_sequence_entry(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Renders `value` the way the scalar at `span` is currently written.
+pub fn render(span: &ScalarSpan, value: &str) -> String {
+    match span.style {
+        ScalarStyle::Literal | ScalarStyle::Folded if scalar::is_block_compatible(value) => {
+            let content_indent = span
+                .content_indent
+                .filter(|indent| *indent > span.key_indent)
+                .unwrap_or(span.key_indent + 2);
+            if span.style == ScalarStyle::Folded {
+                scalar::folded_block_scalar(value, span.key_indent, content_indent)
+            } else {
+                scalar::block_scalar(value, span.key_indent, content_indent)
+            }
+        }
+        ScalarStyle::Plain if scalar::is_plain_safe(value) => value.to_string(),
+        ScalarStyle::SingleQuoted if !value.chars().any(char::is_control) => {
+            scalar::single_quoted(value)
+        }
+        _ => scalar::double_quoted(value),
+    }
+}
+
+/// Replaces the scalar at `span` with `value`, leaving the rest of `content`
+/// untouched.
+pub fn replace(content: &str, span: &ScalarSpan, value: &str) -> String {
+    format!(
+        "{}{}{}",
+        &content[..span.start],
+        render(span, value),
+        &content[span.end..]
+    )
+}
+
+fn split_lines(content: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for raw in content.split_inclusive('\n') {
+        let text = raw.trim_end_matches('\n').trim_end_matches('\r');
+        let indent = text.len() - text.trim_start_matches(' ').len();
+        lines.push(Line {
+            start,
+            text,
+            indent,
+        });
+        start += raw.len();
+    }
+    lines
+}
+
+/// Returns the line range of the `document`-th document that has content,
+/// matching the numbering of `manifest::load_documents`.
+fn document_range(lines: &[Line<'_>], document: usize) -> Option<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut from = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if line.text == "---" || line.text.starts_with("--- ") || line.text == "..." {
+            ranges.push((from, index));
+            from = index + 1;
+        }
+    }
+    ranges.push((from, lines.len()));
+
+    ranges
+        .into_iter()
+        .filter(|(from, to)| lines[*from..*to].iter().any(Line::is_significant))
+        .nth(document)
+}
+
+/// Parses a block mapping key at the start of `text`, returning the key and
+/// the offset just past its `:`.
+fn parse_key(text: &str) -> Option<(String, usize)> {
+    let (key, after) = match text.chars().next()? {
+        '"' => {
+            let end = closing_double_quote(text, 1)?;
+            let key = text[1..end].replace("\\\"", "\"").replace("\\\\", "\\");
+            (key, end + 1)
+        }
+        '\'' => {
+            let end = closing_single_quote(text, 1)?;
+            (text[1..end].replace("''", "'"), end + 1)
+        }
+        '-' | '?' | '#' | '{' | '[' => return None,
+        _ => {
+            let colon = text
+                .match_indices(':')
+                .map(|(index, _)| index)
+                .find(|&index| matches!(text[index + 1..].chars().next(), None | Some(' ')))?;
+            return Some((text[..colon].trim_end().to_string(), colon + 1));
+        }
+    };
+
+    let rest = &text[after..];
+    let colon = rest.len() - rest.trim_start_matches(' ').len();
+    if rest[colon..].starts_with(':') {
+        Some((key, after + colon + 1))
+    } else {
+        None
+    }
+}
+
+fn closing_double_quote(text: &str, from: usize) -> Option<usize> {
+    let mut escaped = false;
+    for (index, ch) in text[from..].char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(from + index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn closing_single_quote(text: &str, from: usize) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut index = from;
+    while index < bytes.len() {
+        if bytes[index] == b'\'' {
+            if bytes.get(index + 1) == Some(&b'\'') {
+                index += 2;
+                continue;
+            }
+            return Some(index);
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Returns the span of the scalar starting at `value_column` of
+/// `lines[index]`, owned by a key or sequence entry at `key_indent`.
+fn scalar_span(
+    lines: &[Line<'_>],
+    index: usize,
+    value_column: usize,
+    key_indent: usize,
+    to: usize,
+) -> Option<ScalarSpan> {
+    let line = &lines[index];
+    let after_colon = &line.text[value_column..];
+    let offset = after_colon.len() - after_colon.trim_start().len();
+    let start_column = value_column + offset;
+    let start = line.start + start_column;
+    let rest = &line.text[start_column..];
+
+    let span = |end, style, content_indent| {
+        Some(ScalarSpan {
+            start,
+            end,
+            style,
+            key_indent,
+            content_indent,
+        })
+    };
+
+    // The scalar after a tag such as `!Sub` is replaced, and the tag kept.
+    if rest.starts_with('!') {
+        let tag_length = rest.find(' ')?;
+        return scalar_span(lines, index, start_column + tag_length, key_indent, to);
+    }
+
+    match rest.chars().next() {
+        None | Some('#' | '{' | '[' | '&' | '*') => None,
+        Some('"') => {
+            let text = joined_text(lines, index, start_column, to);
+            let end = closing_double_quote(&text, 1)?;
+            span(start + end + 1, ScalarStyle::DoubleQuoted, None)
+        }
+        Some('\'') => {
+            let text = joined_text(lines, index, start_column, to);
+            let end = closing_single_quote(&text, 1)?;
+            span(start + end + 1, ScalarStyle::SingleQuoted, None)
+        }
+        Some(indicator @ ('|' | '>')) => {
+            let header = rest.split(" #").next().unwrap_or(rest).trim_end();
+            let keep = header.contains('+');
+            let explicit_indent = header
+                .chars()
+                .find_map(|c| c.to_digit(10))
+                .map(|digit| key_indent + digit as usize);
+
+            let content_lines: Vec<usize> = (index + 1..to)
+                .take_while(|&i| lines[i].is_blank() || lines[i].indent > key_indent)
+                .collect();
+            let last_content = content_lines
+                .iter()
+                .rev()
+                .find(|&&i| !lines[i].is_blank())
+                .copied();
+            let last = if keep {
+                content_lines.last().copied()
+            } else {
+                last_content
+            };
+            let end = last.map_or(line.end(), |i| lines[i].end());
+            let content_indent = explicit_indent.or_else(|| {
+                content_lines
+                    .iter()
+                    .find(|&&i| !lines[i].is_blank())
+                    .map(|&i| lines[i].indent)
+            });
+            let style = if indicator == '|' {
+                ScalarStyle::Literal
+            } else {
+                ScalarStyle::Folded
+            };
+            span(end, style, content_indent)
+        }
+        Some(_) => {
+            let mut end = start + rest.split(" #").next().unwrap_or(rest).trim_end().len();
+            for next in &lines[index + 1..to] {
+                if next.is_blank() || next.indent <= key_indent || !next.is_significant() {
+                    break;
+                }
+                end = next.start + next.text.split(" #").next().unwrap_or("").trim_end().len();
+            }
+            span(end, ScalarStyle::Plain, None)
+        }
+    }
+}
+
+/// Returns the text of `lines[index..to]` starting at `column` of the first
+/// line, joined with line breaks so offsets map back onto the original.
+fn joined_text(lines: &[Line<'_>], index: usize, column: usize, to: usize) -> String {
+    let mut text = String::new();
+    let base = lines[index].start + column;
+    for line in &lines[index..to] {
+        let from = base.max(line.start);
+        // Pad any gap (such as a `\r` before the line break) so byte offsets
+        // stay aligned with the original content.
+        while base + text.len() < from {
+            text.push('\n');
+        }
+        text.push_str(&line.text[from - line.start..]);
+    }
+    text
+}
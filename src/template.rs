@@ -0,0 +1,92 @@
+//! Masking Go template directives, so Helm chart templates can be parsed
+//! as YAML.
+//!
+//! Every `{{ ... }}` expression is swapped for a placeholder before parsing:
+//! a line holding nothing but directives, such as `{{- if .Values.x }}`,
+//! becomes a comment, and an expression inside a line becomes a plain
+//! token. [`Masked::restore`] puts the original text back.
+
+use crate::error::RspError;
+
+const OPEN: &str = "{{";
+const PLACEHOLDER: &str = "__rsp_tpl_";
+const CLOSE: &str = "}}";
+
+/// Template text with its directives replaced by placeholders.
+pub(crate) struct Masked {
+    pub text: String,
+    /// Each placeholder with the template text it stands for.
+    placeholders: Vec<(String, String)>,
+}
+
+impl Masked {
+    /// Replaces the placeholders left in `text` with the template text
+    /// they stand for.
+    pub fn restore(&self, text: &str) -> String {
+        let mut restored = text.to_string();
+        for (placeholder, original) in &self.placeholders {
+            restored = restored.replace(placeholder, original);
+        }
+        restored
+    }
+}
+
+/// Masks the directives of `content`. Fails on a `{{` that is never
+/// closed.
+pub(crate) fn mask(content: &str) -> Result<Masked, RspError> {
+    let mut text = String::with_capacity(content.len());
+    let mut placeholders = Vec::new();
+    let mut copied = 0;
+
+    while let Some(found) = content[copied..].find(OPEN) {
+        let open = copied + found;
+        let mut end = closing(content, open)?;
+        // Directives separated only by spaces form one run.
+        loop {
+            let rest = &content[end..];
+            let gap = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            if !rest[gap..].starts_with(OPEN) {
+                break;
+            }
+            end = closing(content, end + gap)?;
+        }
+
+        let line_start = content[..open].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = content[end..]
+            .find('\n')
+            .map_or(content.len(), |index| end + index);
+        let whole_line = content[line_start..open]
+            .trim_start_matches([' ', '\t'])
+            .is_empty()
+            && content[end..line_end].trim_end().is_empty();
+
+        let placeholder = if whole_line {
+            format!("#{PLACEHOLDER}{}__", placeholders.len())
+        } else {
+            format!("{PLACEHOLDER}{}__", placeholders.len())
+        };
+        text.push_str(&content[copied..open]);
+        text.push_str(&placeholder);
+        placeholders.push((placeholder, content[open..end].to_string()));
+        copied = end;
+    }
+    text.push_str(&content[copied..]);
+
+    Ok(Masked { text, placeholders })
+}
+
+/// Whether `text` holds a masked directive.
+pub(crate) fn contains_placeholder(text: &str) -> bool {
+    text.contains(PLACEHOLDER)
+}
+
+/// The offset just past the `}}` closing the `{{` at `open`.
+fn closing(content: &str, open: usize) -> Result<usize, RspError> {
+    content[open + OPEN.len()..]
+        .find(CLOSE)
+        .map(|close| open + OPEN.len() + close + CLOSE.len())
+        .ok_or_else(|| {
+            let line = content[..open].matches('\n').count() + 1;
+            RspError::InvalidFormat(format!("unclosed template directive on line {line}"))
+        })
+}
@@ -0,0 +1,78 @@
+//! TOML as the outer document format.
+//!
+//! A TOML file is peeled through its data model like any manifest, and the
+//! peeled strings are then written into a [`DocumentMut`] of the original
+//! text, so comments, table order and the other values keep their layout.
+//! Multi-line strings are written as `'''` literal strings, or as `"""`
+//! basic strings when they hold characters a literal string cannot.
+
+use crate::error::RspError;
+use crate::selector::PathSegment;
+use serde_yaml::Value;
+use toml_edit::{DocumentMut, Formatted, Item};
+
+/// Reads `content` into the data model peeling works on.
+pub(crate) fn load(content: &str) -> Result<Value, RspError> {
+    toml::from_str(content).map_err(|e| RspError::InvalidFormat(format!("invalid TOML: {e}")))
+}
+
+/// Parses `content` for editing in place.
+pub(crate) fn parse(content: &str) -> Result<DocumentMut, RspError> {
+    content
+        .parse()
+        .map_err(|e| RspError::InvalidFormat(format!("invalid TOML: {e}")))
+}
+
+/// Replaces the string at `path` with `value`, keeping the comments and
+/// whitespace around it. A multi-line string that already holds `value`
+/// is left as written. Returns `false` when `path` leads to no string.
+pub(crate) fn set_string(document: &mut DocumentMut, path: &[PathSegment], value: &str) -> bool {
+    let mut item = document.as_item_mut();
+    for segment in path {
+        let next = match segment {
+            PathSegment::Key(key) => item.get_mut(key.as_str()),
+            PathSegment::Index(index) => item.get_mut(*index),
+        };
+        let Some(next) = next else {
+            return false;
+        };
+        item = next;
+    }
+    let Item::Value(toml_edit::Value::String(current)) = item else {
+        return false;
+    };
+
+    if current.value() == value && is_multi_line(current) {
+        return true;
+    }
+    let decor = current.decor().clone();
+    let mut replacement = multi_line_literal(value)
+        .unwrap_or_else(|| toml_edit::Value::String(Formatted::new(value.to_string())));
+    *replacement.decor_mut() = decor;
+    *item = Item::Value(replacement);
+    true
+}
+
+/// `value` as a `'''` literal string, which needs no escapes. `None` when
+/// it is a single line, or holds `'''` or control characters a literal
+/// string cannot.
+fn multi_line_literal(value: &str) -> Option<toml_edit::Value> {
+    let writable = value.contains('\n')
+        && !value.contains("'''")
+        && !value
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\n' | '\t'));
+    if !writable {
+        return None;
+    }
+    // The newline right after the opening quotes is not part of the value.
+    let literal: toml_edit::Value = format!("'''\n{value}'''").parse().ok()?;
+    (literal.as_str() == Some(value)).then_some(literal)
+}
+
+fn is_multi_line(string: &Formatted<String>) -> bool {
+    string
+        .as_repr()
+        .and_then(|repr| repr.as_raw().as_str())
+        .is_some_and(|raw| raw.starts_with("'''") || raw.starts_with("\"\"\""))
+}
@@ -0,0 +1,115 @@
+use rsp_cli::config::Config;
+use rsp_cli::{AnnotationMode, EntryStatus, PeelOptions, Peeler};
+use std::process::Command;
+
+const DEPLOYMENT: &str = r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  annotations:
+    kubectl.kubernetes.io/last-applied-configuration: '{"kind":"Deployment","apiVersion":"apps/v1","spec":{"args":["a\nb"]}}'
+    example.com/settings: '{"b":1,"a":true}'
+    owner: team-a
+spec:
+  replicas: 2
+"#;
+
+#[test]
+fn test_annotations_are_kept_by_default() {
+    let output = Peeler::new().peel_str(DEPLOYMENT).unwrap();
+    assert!(output.text.contains(
+        r#"    kubectl.kubernetes.io/last-applied-configuration: "{\"kind\":\"Deployment\","#
+    ));
+    assert!(output.report.entries.is_empty());
+}
+
+#[test]
+fn test_peel_last_applied_and_extra_annotations() {
+    let options = PeelOptions::new().annotations(AnnotationMode::Peel);
+    let output = Peeler::with_options(options).peel_str(DEPLOYMENT).unwrap();
+
+    // Pretty-printed with sorted keys; escapes inside JSON strings are kept
+    assert!(output.text.contains(
+        "    kubectl.kubernetes.io/last-applied-configuration: |\n      {\n        \"apiVersion\": \"apps/v1\",\n        \"kind\": \"Deployment\",\n        \"spec\": {\n          \"args\": [\n            \"a\\nb\"\n          ]\n        }\n      }\n"
+    ));
+    assert!(output.text.contains("    example.com/settings: "));
+    assert_eq!(output.report.entries.len(), 1);
+    assert_eq!(
+        output.report.entries[0].path,
+        r#"metadata.annotations["kubectl.kubernetes.io/last-applied-configuration"]"#
+    );
+    assert_eq!(output.report.entries[0].format.as_deref(), Some("json"));
+
+    let options = PeelOptions::new()
+        .annotations(AnnotationMode::Peel)
+        .annotation("example.com/settings");
+    let output = Peeler::with_options(options).peel_str(DEPLOYMENT).unwrap();
+    assert!(output.text.contains(
+        "    example.com/settings: |\n      {\n        \"a\": true,\n        \"b\": 1\n      }\n"
+    ));
+    assert_eq!(output.report.peeled().count(), 2);
+
+    // Annotations that are not JSON are reported and left alone
+    let options = PeelOptions::new()
+        .annotations(AnnotationMode::Peel)
+        .annotation("owner");
+    let output = Peeler::with_options(options).peel_str(DEPLOYMENT).unwrap();
+    assert!(output.text.contains("    owner: team-a\n"));
+    assert!(matches!(
+        output.report.entries[1].status,
+        EntryStatus::Failed { .. }
+    ));
+}
+
+#[test]
+fn test_strip_annotations() {
+    let options = PeelOptions::new()
+        .annotations(AnnotationMode::Strip)
+        .annotation("owner");
+    let output = Peeler::with_options(options).peel_str(DEPLOYMENT).unwrap();
+    assert!(!output.text.contains("last-applied-configuration"));
+    assert!(
+        output
+            .text
+            .contains("  annotations:\n    example.com/settings: ")
+    );
+
+    let options = PeelOptions::new()
+        .annotations(AnnotationMode::Strip)
+        .annotation("owner")
+        .annotation("example.com/settings");
+    let output = Peeler::with_options(options).peel_str(DEPLOYMENT).unwrap();
+    assert!(output.text.contains("metadata:\n  name: web\nspec:\n"));
+}
+
+#[test]
+fn test_annotation_settings_and_cli() {
+    let config =
+        Config::from_toml("annotations = \"strip\"\nannotation_keys = [\"owner\"]\n").unwrap();
+    let output = Peeler::with_options(config.apply(PeelOptions::new()))
+        .peel_str(DEPLOYMENT)
+        .unwrap();
+    assert!(!output.text.contains("owner"));
+    assert!(Config::from_toml("annotations = \"hide\"\n").is_err());
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("deployment.yaml");
+    std::fs::write(&input, DEPLOYMENT).unwrap();
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--annotations",
+            "peel",
+            "--annotation",
+            "example.com/settings",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("    kubectl.kubernetes.io/last-applied-configuration: |\n"));
+    assert!(stdout.contains("    example.com/settings: |\n"));
+}
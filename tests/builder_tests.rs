@@ -0,0 +1,109 @@
+use rsp_cli::builder::ConfigMapBuilder;
+use rsp_cli::error::RspError;
+use serde_yaml::Value;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_build_from_directory() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.json"),
+        "{\n  \"hello\": \"world\"\n}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("app.toml"), "name = \"demo\"").unwrap();
+    fs::create_dir(dir.path().join("nested")).unwrap();
+
+    let rendered = ConfigMapBuilder::new("app-config")
+        .namespace("prod")
+        .source(dir.path().to_str().unwrap())
+        .render()
+        .unwrap();
+
+    assert!(rendered.contains("kind: ConfigMap"));
+    assert!(rendered.contains("  name: app-config"));
+    assert!(rendered.contains("  namespace: prod"));
+    assert!(rendered.contains("  config.json: |\n    {\n      \"hello\": \"world\"\n    }\n"));
+    assert!(rendered.contains("  app.toml: name = \"demo\""));
+    assert!(!rendered.contains("nested"));
+
+    // The rendered manifest must read back to exactly the file contents
+    let parsed: Value = serde_yaml::from_str(&rendered).unwrap();
+    assert_eq!(
+        parsed["data"]["config.json"].as_str(),
+        Some("{\n  \"hello\": \"world\"\n}\n")
+    );
+    assert_eq!(parsed["data"]["app.toml"].as_str(), Some("name = \"demo\""));
+}
+
+#[test]
+fn test_build_with_explicit_key_and_metadata() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("settings.yaml");
+    fs::write(&path, "a: 1\nb: 2").unwrap();
+
+    let source = format!("app.yaml={}", path.display());
+    let manifest = ConfigMapBuilder::new("app-config")
+        .label("app", "demo")
+        .label("enabled", "true")
+        .annotation("owner", "platform-team")
+        .source(&source)
+        .build()
+        .unwrap();
+
+    assert_eq!(manifest["data"]["app.yaml"].as_str(), Some("a: 1\nb: 2"));
+    assert_eq!(manifest["metadata"]["labels"]["app"].as_str(), Some("demo"));
+    assert_eq!(
+        manifest["metadata"]["annotations"]["owner"].as_str(),
+        Some("platform-team")
+    );
+
+    // Label values that look like booleans must stay strings after rendering
+    let rendered = ConfigMapBuilder::new("app-config")
+        .label("enabled", "true")
+        .source(&source)
+        .render()
+        .unwrap();
+    assert!(rendered.contains("enabled: \"true\""));
+    assert!(rendered.contains("  app.yaml: |-\n    a: 1\n    b: 2\n"));
+}
+
+#[test]
+fn test_build_binary_file() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("logo.png"), [0x89, 0x50, 0xFF, 0xFE]).unwrap();
+
+    let manifest = ConfigMapBuilder::new("assets")
+        .source(dir.path().join("logo.png").to_str().unwrap())
+        .build()
+        .unwrap();
+
+    assert!(manifest.get("data").is_none());
+    assert_eq!(
+        manifest["binaryData"]["logo.png"].as_str(),
+        Some("iVD//g==")
+    );
+}
+
+#[test]
+fn test_build_errors() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("config.json");
+    fs::write(&path, "{}").unwrap();
+    let path = path.to_str().unwrap();
+
+    let result = ConfigMapBuilder::new("cm").source("missing.json").build();
+    assert!(matches!(result, Err(RspError::FileNotFound(_))));
+
+    let result = ConfigMapBuilder::new("cm")
+        .source(path)
+        .source(&format!("config.json={path}"))
+        .build();
+    assert!(matches!(result, Err(RspError::InvalidFormat(_))));
+
+    let result = ConfigMapBuilder::new("cm")
+        .source(&format!("bad/key={path}"))
+        .build();
+    assert!(matches!(result, Err(RspError::InvalidFormat(_))));
+}
@@ -185,6 +185,43 @@ data:
     assert!(stdout.contains("  \"foo\":\"bar\""));
 }
 
+#[test]
+fn test_cli_peel_json_report() {
+    let mut temp_file = NamedTempFile::new().unwrap();
+    write!(
+        temp_file,
+        "kind: ConfigMap\nmetadata:\n  name: app\ndata:\n  app.yaml: \"a: 1\\nb: 2\"\n  notes.txt: hi\n"
+    )
+    .unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            temp_file.path().to_str().unwrap(),
+            "--report",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("app.yaml: |-"));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let json_start = stderr.find("{\n").expect("report on stderr");
+    let report: serde_json::Value = serde_json::from_str(&stderr[json_start..]).unwrap();
+    let entries = report["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["key"], "app.yaml");
+    assert_eq!(entries[0]["status"], "peeled");
+    assert_eq!(entries[0]["format"], "yaml");
+    assert_eq!(entries[1]["status"], "skipped");
+    assert_eq!(entries[1]["reason"], "unsupported file type");
+}
+
 #[test]
 fn test_cli_peel_stdin_with_output_file() {
     let yaml_content = r#"apiVersion: v1
@@ -224,3 +261,246 @@ data:
     let stdout = String::from_utf8(output.stdout).unwrap();
     assert!(stdout.contains("Output written to"));
 }
+
+#[test]
+fn test_cli_build() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("config.yaml"), "hello: world\nfoo: bar\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "build",
+            dir.path().to_str().unwrap(),
+            "--name",
+            "example-config",
+            "-n",
+            "default",
+            "-l",
+            "app=example",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("  name: example-config"));
+    assert!(stdout.contains("    app: example"));
+    assert!(stdout.contains("  config.yaml: |\n    hello: world\n    foo: bar\n"));
+}
+
+#[test]
+fn test_cli_build_invalid_label() {
+    let dir = tempfile::TempDir::new().unwrap();
+    fs::write(dir.path().join("config.yaml"), "hello: world\n").unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "build",
+            dir.path().to_str().unwrap(),
+            "--name",
+            "example-config",
+            "--label",
+            "missing-separator",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_cli_get() {
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  config.json: "{\n  \"hello\":\"world\"\n}"
+  other.yaml: "a: b"
+"#;
+
+    let mut input_file = NamedTempFile::new().unwrap();
+    write!(input_file, "{yaml_content}").unwrap();
+    let input_path = input_file.path().to_str().unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "get", input_path, "config.json"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "{\n  \"hello\":\"world\"\n}\n");
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "get", input_path, "missing.json"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("missing.json"));
+}
+
+#[test]
+fn test_cli_edit() {
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  config.json: "{\"hello\":\"world\"}"
+"#;
+
+    let mut input_file = NamedTempFile::new().unwrap();
+    write!(input_file, "{yaml_content}").unwrap();
+    let input_path = input_file.path().to_str().unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "edit", input_path, "config.json"])
+        .env("VISUAL", "perl -pi -e s/world/there/")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let file_content = fs::read_to_string(input_path).unwrap();
+    assert_eq!(
+        file_content,
+        yaml_content.replace("world", "there"),
+        "only the edited value should change"
+    );
+
+    // An edit that breaks the JSON is rejected and the file is left alone
+    let output = Command::new("cargo")
+        .args(["run", "--", "edit", input_path, "config.json"])
+        .env("VISUAL", "perl -pi -e s/}/,/")
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("invalid JSON"));
+    let file_content = fs::read_to_string(input_path).unwrap();
+    assert!(file_content.contains("there"));
+}
+
+#[test]
+fn test_cli_diff() {
+    let old_content = r#"kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  app.yaml: "hello: world\nfoo: bar"
+"#;
+    let new_content = r#"kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  app.yaml: "hello: there\nfoo: bar"
+"#;
+
+    let mut old_file = NamedTempFile::new().unwrap();
+    write!(old_file, "{old_content}").unwrap();
+    let mut new_file = NamedTempFile::new().unwrap();
+    write!(new_file, "{new_content}").unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "diff",
+            old_file.path().to_str().unwrap(),
+            new_file.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("-hello: world"));
+    assert!(stdout.contains("+hello: there"));
+    assert!(stdout.contains(" foo: bar"));
+    assert!(stdout.contains("~ .hello: \"world\" -> \"there\""));
+}
+
+#[test]
+fn test_cli_textconv_never_fails() {
+    let output = Command::new("cargo")
+        .args(["run", "--", "textconv", "nonexistent_file.yaml"])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+
+    let invalid_yaml = "invalid: yaml: content: [unclosed";
+    let mut input_file = NamedTempFile::new().unwrap();
+    write!(input_file, "{invalid_yaml}").unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "textconv", input_file.path().to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), invalid_yaml);
+}
+
+#[test]
+fn test_cli_merge_driver() {
+    let base = "kind: ConfigMap\ndata:\n  app.yaml: \"a: 1\\nb: 2\\nc: 3\"\n";
+    let ours = "kind: ConfigMap\ndata:\n  app.yaml: \"a: 10\\nb: 2\\nc: 3\"\n";
+    let theirs = "kind: ConfigMap\ndata:\n  app.yaml: \"a: 1\\nb: 2\\nc: 30\"\n";
+
+    let write_temp = |content: &str| {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(file, "{content}").unwrap();
+        file
+    };
+    let base_file = write_temp(base);
+    let ours_file = write_temp(ours);
+    let theirs_file = write_temp(theirs);
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "merge-driver",
+            base_file.path().to_str().unwrap(),
+            ours_file.path().to_str().unwrap(),
+            theirs_file.path().to_str().unwrap(),
+            "--marker-size",
+            "7",
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(output.status.success());
+    let merged = fs::read_to_string(ours_file.path()).unwrap();
+    assert_eq!(
+        merged,
+        "kind: ConfigMap\ndata:\n  app.yaml: \"a: 10\\nb: 2\\nc: 30\"\n"
+    );
+
+    // Conflicting edits leave markers and fail so git reports the conflict
+    let conflicting = write_temp("kind: ConfigMap\ndata:\n  app.yaml: \"a: 1\\nb: 2\\nc: 31\"\n");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "merge-driver",
+            base_file.path().to_str().unwrap(),
+            conflicting.path().to_str().unwrap(),
+            theirs_file.path().to_str().unwrap(),
+        ])
+        .output()
+        .expect("Failed to execute command");
+
+    assert!(!output.status.success());
+    let merged = fs::read_to_string(conflicting.path()).unwrap();
+    assert!(merged.contains("  app.yaml: |-\n    a: 1\n    b: 2\n    <<<<<<< ours\n    c: 31\n"));
+}
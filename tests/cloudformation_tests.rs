@@ -0,0 +1,197 @@
+use rsp_cli::{PeelOptions, Peeler, Profile};
+use serde_yaml::Value;
+use std::process::Command;
+
+const TEMPLATE: &str = r##"AWSTemplateFormatVersion: "2010-09-09"
+Transform: AWS::Serverless-2016-10-31
+Resources:
+  StateMachine:
+    Type: AWS::StepFunctions::StateMachine
+    Properties:
+      RoleArn: !GetAtt Role.Arn
+      DefinitionString: !Sub '{"StartAt":"Hello","States":{"Hello":{"Type":"Pass","Result":"${AWS::Region}","End":true}}}'
+  Instance:
+    Type: AWS::EC2::Instance
+    Properties:
+      ImageId: !Ref ImageId
+      Tags:
+        - Key: Name
+          Value: !Join ["-", [!Ref "AWS::StackName", web]]
+      UserData:
+        Fn::Base64: !Sub "#!/bin/bash\nyum update -y\necho ${AWS::StackName}\n"
+  Role:
+    Type: AWS::IAM::Role
+    Properties:
+      AssumeRolePolicyDocument:
+        Version: "2012-10-17"
+        Statement:
+          - Effect: Allow
+            Principal:
+              Service: ec2.amazonaws.com
+            Action: sts:AssumeRole
+      Policies:
+        - PolicyName: s3
+          PolicyDocument: '{"Version":"2012-10-17","Statement":[]}'
+"##;
+
+#[test]
+fn test_cloudformation_profile_peels_tagged_strings() {
+    let output = Peeler::new().peel_str(TEMPLATE).unwrap();
+    assert_eq!(
+        output.text,
+        r##"AWSTemplateFormatVersion: "2010-09-09"
+Transform: AWS::Serverless-2016-10-31
+Resources:
+  StateMachine:
+    Type: AWS::StepFunctions::StateMachine
+    Properties:
+      RoleArn: !GetAtt Role.Arn
+      DefinitionString: !Sub |
+        {
+          "StartAt": "Hello",
+          "States": {
+            "Hello": {
+              "End": true,
+              "Result": "${AWS::Region}",
+              "Type": "Pass"
+            }
+          }
+        }
+  Instance:
+    Type: AWS::EC2::Instance
+    Properties:
+      ImageId: !Ref ImageId
+      Tags:
+        - Key: Name
+          Value: !Join
+            - "-"
+            - - !Ref AWS::StackName
+              - web
+      UserData:
+        Fn::Base64: !Sub |
+          #!/bin/bash
+          yum update -y
+          echo ${AWS::StackName}
+  Role:
+    Type: AWS::IAM::Role
+    Properties:
+      AssumeRolePolicyDocument:
+        Version: "2012-10-17"
+        Statement:
+          - Effect: Allow
+            Principal:
+              Service: ec2.amazonaws.com
+            Action: sts:AssumeRole
+      Policies:
+        - PolicyName: s3
+          PolicyDocument: |
+            {
+              "Statement": [],
+              "Version": "2012-10-17"
+            }
+"##
+    );
+
+    // Policies written as YAML are no embedded files
+    let paths: Vec<&str> = output
+        .report
+        .entries
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "Resources.StateMachine.Properties.DefinitionString",
+            "Resources.Role.Properties.Policies[0].PolicyDocument",
+            r#"Resources.Instance.Properties.UserData["Fn::Base64"]"#,
+        ]
+    );
+}
+
+#[test]
+fn test_cloudformation_tags_survive_peel_and_pack() {
+    let peeler = Peeler::new();
+    let original: Value = serde_yaml::from_str(TEMPLATE).unwrap();
+    let mut document = original.clone();
+    peeler.peel_value(&mut document).unwrap();
+    peeler.pack_value(&mut document).unwrap();
+
+    let Value::Tagged(definition) =
+        &document["Resources"]["StateMachine"]["Properties"]["DefinitionString"]
+    else {
+        panic!("the !Sub tag was lost");
+    };
+    assert_eq!(definition.tag, "!Sub");
+    assert!(!definition.value.as_str().unwrap().contains('\n'));
+    assert_eq!(
+        document["Resources"]["Instance"]["Properties"]["Tags"],
+        original["Resources"]["Instance"]["Properties"]["Tags"]
+    );
+    let Value::Tagged(user_data) =
+        &document["Resources"]["Instance"]["Properties"]["UserData"]["Fn::Base64"]
+    else {
+        panic!("the !Sub tag was lost");
+    };
+    assert_eq!(user_data.tag, "!Sub");
+}
+
+#[test]
+fn test_cloudformation_profile_needs_resources_and_can_be_turned_off() {
+    let options = PeelOptions::new().profile(Profile::CloudFormation, false);
+    let output = Peeler::with_options(options).peel_str(TEMPLATE).unwrap();
+    assert!(output.report.entries.is_empty());
+    // The tags are still written back
+    assert!(
+        output
+            .text
+            .contains("      DefinitionString: !Sub \"{\\\"StartAt\\\":")
+    );
+
+    let output = Peeler::new()
+        .peel_str("Properties:\n  UserData: \"#!/bin/sh\\necho hi\\n\"\n")
+        .unwrap();
+    assert!(output.report.entries.is_empty());
+}
+
+#[test]
+fn test_template_mode_keeps_tags_and_flow_style() {
+    let options = PeelOptions::new().template(true);
+    let output = Peeler::with_options(options).peel_str(TEMPLATE).unwrap();
+    assert!(
+        output
+            .text
+            .contains("          Value: !Join [\"-\", [!Ref \"AWS::StackName\", web]]\n")
+    );
+    assert!(output.text.contains(
+        "        Fn::Base64: !Sub |\n          #!/bin/bash\n          yum update -y\n          echo ${AWS::StackName}\n"
+    ));
+    assert!(
+        output
+            .text
+            .contains("      DefinitionString: !Sub |\n        {\n")
+    );
+}
+
+#[test]
+fn test_cli_cloudformation_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("template.yaml");
+    std::fs::write(&input, TEMPLATE).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--no-profile",
+            "cloudformation",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("AWSTemplateFormatVersion: \"2010-09-09\"\n"));
+    assert!(stdout.contains("      RoleArn: !GetAtt Role.Arn\n"));
+}
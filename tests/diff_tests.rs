@@ -0,0 +1,73 @@
+use rsp_cli::diff::{StructuralChange, diff_manifests};
+use rsp_cli::peeler::Peeler;
+use serde_json::json;
+
+const OLD: &str = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+  namespace: prod
+data:
+  config.json: "{\"server\":{\"port\":8080},\"debug\":true}"
+  unchanged.yaml: "a: 1"
+  removed.txt: gone
+"#;
+
+const NEW: &str = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+  namespace: prod
+data:
+  config.json: |
+    {"server": {"port": 9090}, "tags": ["a"]}
+  unchanged.yaml: |-
+    a: 1
+"#;
+
+#[test]
+fn test_diff_matches_resources_and_keys() {
+    let diffs = diff_manifests(&Peeler::new(), OLD, NEW).unwrap();
+
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(diffs[0].resource, "ConfigMap/prod/app-config");
+    assert_eq!(diffs[0].key, "config.json");
+    assert_eq!(diffs[1].key, "removed.txt");
+    assert_eq!(diffs[1].old.as_deref(), Some("gone"));
+    assert!(diffs[1].new.is_none());
+}
+
+#[test]
+fn test_diff_structural_changes() {
+    let diffs = diff_manifests(&Peeler::new(), OLD, NEW).unwrap();
+
+    assert_eq!(
+        diffs[0].structural,
+        vec![
+            StructuralChange::Removed {
+                path: ".debug".to_string(),
+                value: json!(true),
+            },
+            StructuralChange::Changed {
+                path: ".server.port".to_string(),
+                old: json!(8080),
+                new: json!(9090),
+            },
+            StructuralChange::Added {
+                path: ".tags".to_string(),
+                value: json!(["a"]),
+            },
+        ]
+    );
+
+    let rendered = diffs[0].to_string();
+    assert!(rendered.contains("--- a/ConfigMap/prod/app-config/config.json"));
+    assert!(rendered.contains("+++ b/ConfigMap/prod/app-config/config.json"));
+    assert!(rendered.contains("~ .server.port: 8080 -> 9090"));
+}
+
+#[test]
+fn test_diff_identical_manifests() {
+    let diffs = diff_manifests(&Peeler::new(), OLD, OLD).unwrap();
+    assert!(diffs.is_empty());
+}
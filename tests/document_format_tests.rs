@@ -0,0 +1,145 @@
+use rsp_cli::{DocumentFormat, PeelOptions, Peeler, RspError};
+use std::process::Command;
+
+const CONFIGMAP_JSON: &str = r#"{
+    "apiVersion": "v1",
+    "kind": "ConfigMap",
+    "metadata": {
+        "name": "app"
+    },
+    "data": {
+        "config.json": "{\"replicas\":2}",
+        "app.yaml": "a: 1\nb: 2\n"
+    }
+}
+"#;
+
+#[test]
+fn test_json_input_is_written_back_as_json() {
+    let output = Peeler::new().peel_str(CONFIGMAP_JSON).unwrap();
+    assert_eq!(
+        output.text,
+        r#"{
+    "apiVersion": "v1",
+    "kind": "ConfigMap",
+    "metadata": {
+        "name": "app"
+    },
+    "data": {
+        "config.json": "{\n  \"replicas\": 2\n}\n",
+        "app.yaml": "a: 1\nb: 2\n"
+    }
+}
+"#
+    );
+    assert_eq!(output.report.entries.len(), 2);
+    assert_eq!(output.report.entries[0].format.as_deref(), Some("json"));
+}
+
+#[test]
+fn test_json_input_peeled_into_yaml() {
+    let options = PeelOptions::new().output_format(DocumentFormat::Yaml);
+    let output = Peeler::with_options(options)
+        .peel_str(CONFIGMAP_JSON)
+        .unwrap();
+    assert_eq!(
+        output.text,
+        "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: app\ndata:\n  config.json: \"{\\\"replicas\\\":2}\"\n  app.yaml: |\n    a: 1\n    b: 2\n"
+    );
+}
+
+#[test]
+fn test_yaml_input_written_as_json() {
+    let yaml = "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: a\ndata:\n  config.json: '{\"x\":1}'\n---\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: b\n";
+    let options = PeelOptions::new().output_format(DocumentFormat::Json);
+    let output = Peeler::with_options(options).peel_str(yaml).unwrap();
+
+    // One JSON value per document
+    let values: Vec<serde_json::Value> = serde_json::Deserializer::from_str(&output.text)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0]["data"]["config.json"], "{\n  \"x\": 1\n}\n");
+    assert_eq!(values[1]["metadata"]["name"], "b");
+}
+
+#[test]
+fn test_input_format_detection() {
+    assert_eq!(
+        DocumentFormat::detect("  {\"kind\": \"List\"}"),
+        DocumentFormat::Json
+    );
+    assert_eq!(DocumentFormat::detect("kind: List"), DocumentFormat::Yaml);
+
+    // A YAML flow mapping is read as YAML once it fails to parse as JSON
+    let flow = "{apiVersion: v1, kind: ConfigMap, data: {app.yaml: \"a: 1\\nb: 2\"}}\n";
+    let output = Peeler::new().peel_str(flow).unwrap();
+    assert!(output.text.contains("  app.yaml: |-\n    a: 1\n    b: 2\n"));
+
+    let options = PeelOptions::new().input_format(DocumentFormat::Json);
+    match Peeler::with_options(options).peel_str(flow) {
+        Err(RspError::InvalidFormat(message)) => assert!(message.starts_with("invalid JSON")),
+        other => panic!("expected an invalid JSON error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_json_list_items_are_peeled() {
+    let list = r#"{"apiVersion": "v1", "kind": "List", "items": [
+        {"apiVersion": "v1", "kind": "ConfigMap", "metadata": {"name": "a"}, "data": {"config.json": "{\"a\":1}"}}
+    ]}"#;
+    let output = Peeler::new().peel_str(list).unwrap();
+    assert!(
+        output
+            .text
+            .contains(r#""config.json": "{\n  \"a\": 1\n}\n""#)
+    );
+    assert_eq!(
+        output.report.entries[0].path,
+        r#"items[0].data["config.json"]"#
+    );
+}
+
+#[test]
+fn test_cli_document_formats() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("configmap.json");
+    std::fs::write(&input, CONFIGMAP_JSON).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "peel", input.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{\n    \"apiVersion\": \"v1\",\n"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--output-format",
+            "yaml",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("  app.yaml: |\n    a: 1\n    b: 2\n"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--output-format",
+            "toml",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
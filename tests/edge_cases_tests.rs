@@ -56,18 +56,18 @@ data:
     assert!(result.is_ok());
 
     // Check that only string values with proper extensions are processed
-    if let Value::Mapping(map) = &yaml_value {
-        if let Some(Value::Mapping(data)) = map.get(Value::String("data".to_string())) {
-            // Number and boolean should remain unchanged
-            assert!(matches!(
-                data.get(Value::String("number-value".to_string())),
-                Some(Value::Number(_))
-            ));
-            assert!(matches!(
-                data.get(Value::String("boolean-value".to_string())),
-                Some(Value::Bool(_))
-            ));
-        }
+    if let Value::Mapping(map) = &yaml_value
+        && let Some(Value::Mapping(data)) = map.get(Value::String("data".to_string()))
+    {
+        // Number and boolean should remain unchanged
+        assert!(matches!(
+            data.get(Value::String("number-value".to_string())),
+            Some(Value::Number(_))
+        ));
+        assert!(matches!(
+            data.get(Value::String("boolean-value".to_string())),
+            Some(Value::Bool(_))
+        ));
     }
 }
 
@@ -139,16 +139,13 @@ not_a_configmap:
     assert!(result.is_ok());
 
     // Should not process data since it's not a proper ConfigMap
-    if let Value::Mapping(map) = &yaml_value {
-        if let Some(Value::Mapping(inner)) = map.get(Value::String("not_a_configmap".to_string())) {
-            if let Some(Value::Mapping(_data)) = inner.get(Value::String("data".to_string())) {
-                if let Some(Value::String(json_content)) =
-                    _data.get(Value::String("config.json".to_string()))
-                {
-                    assert_eq!(json_content, "{\"hello\":\"world\"}"); // Should remain escaped
-                }
-            }
-        }
+    if let Value::Mapping(map) = &yaml_value
+        && let Some(Value::Mapping(inner)) = map.get(Value::String("not_a_configmap".to_string()))
+        && let Some(Value::Mapping(_data)) = inner.get(Value::String("data".to_string()))
+        && let Some(Value::String(json_content)) =
+            _data.get(Value::String("config.json".to_string()))
+    {
+        assert_eq!(json_content, "{\"hello\":\"world\"}"); // Should remain escaped
     }
 }
 
@@ -219,18 +216,18 @@ data:
     let mut yaml_value: Value = serde_yaml::from_str(yaml_content).unwrap();
     peeler.process_yaml_value(&mut yaml_value).unwrap();
 
-    if let Value::Mapping(map) = &yaml_value {
-        if let Some(Value::Mapping(_data)) = map.get(Value::String("data".to_string())) {
-            // These should be processed (unescaped)
-            assert!(peeler.should_process_key("config.json"));
-            assert!(peeler.should_process_key("config.yaml"));
-            assert!(peeler.should_process_key("config.yml"));
-            assert!(peeler.should_process_key("config.toml"));
-
-            // These should not be processed
-            assert!(!peeler.should_process_key("config.txt"));
-            assert!(!peeler.should_process_key("config.xml"));
-            assert!(!peeler.should_process_key("no-extension"));
-        }
+    if let Value::Mapping(map) = &yaml_value
+        && let Some(Value::Mapping(_data)) = map.get(Value::String("data".to_string()))
+    {
+        // These should be processed (unescaped)
+        assert!(peeler.should_process_key("config.json"));
+        assert!(peeler.should_process_key("config.yaml"));
+        assert!(peeler.should_process_key("config.yml"));
+        assert!(peeler.should_process_key("config.toml"));
+
+        // These should not be processed
+        assert!(!peeler.should_process_key("config.txt"));
+        assert!(!peeler.should_process_key("config.xml"));
+        assert!(!peeler.should_process_key("no-extension"));
     }
 }
@@ -0,0 +1,156 @@
+use rsp_cli::edit;
+use rsp_cli::error::RspError;
+use rsp_cli::peeler::Peeler;
+
+#[test]
+fn test_replace_keeps_escaped_style_and_rest_of_file() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"# Managed by hand
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: example-config  # trailing comment
+data:
+  config.json: "{\"hello\":\"world\"}"
+  other.txt: untouched
+"#;
+
+    let result = peeler
+        .replace(
+            yaml_content,
+            "config.json",
+            None,
+            "{\n  \"hello\": \"there\"\n}",
+        )
+        .unwrap();
+
+    assert_eq!(
+        result,
+        r#"# Managed by hand
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: example-config  # trailing comment
+data:
+  config.json: "{\n  \"hello\": \"there\"\n}"
+  other.txt: untouched
+"#
+    );
+}
+
+#[test]
+fn test_replace_keeps_block_style() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: first
+data:
+  app.yaml: x
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: second
+data:
+  app.yaml: |
+      hello: world
+      foo: bar
+
+  after: value
+"#;
+
+    let result = peeler
+        .replace(yaml_content, "app.yaml", Some("second"), "hello: there\n")
+        .unwrap();
+
+    assert!(result.contains("  app.yaml: x\n"));
+    assert!(result.contains("  app.yaml: |\n      hello: there\n\n  after: value\n"));
+    assert_eq!(
+        peeler.extract(&result, "app.yaml", Some("second")).unwrap(),
+        "hello: there\n"
+    );
+}
+
+#[test]
+fn test_replace_keeps_folded_style() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: notes
+data:
+  motd.txt: >
+    Welcome to the cluster.
+    Please be nice.
+  after: value
+"#;
+
+    let value = "Welcome to the staging cluster, which is reset every night and shared by every team.\nAsk before deploying.\n\n  kubectl get pods\n";
+    let result = peeler
+        .replace(yaml_content, "motd.txt", None, value)
+        .unwrap();
+
+    assert!(result.contains(
+        "  motd.txt: >\n    Welcome to the staging cluster, which is reset every night and shared by\n    every team.\n\n    Ask before deploying.\n\n      kubectl get pods\n  after: value\n"
+    ));
+    assert_eq!(peeler.extract(&result, "motd.txt", None).unwrap(), value);
+}
+
+#[test]
+fn test_replace_double_escaped_value() {
+    let peeler = Peeler::new();
+
+    // The YAML string itself holds literal `\n` sequences
+    let yaml_content = r#"kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  config.yaml: 'a: 1\nb: 2'
+"#;
+
+    let current = peeler.extract(yaml_content, "config.yaml", None).unwrap();
+    assert_eq!(current, "a: 1\nb: 2");
+
+    let result = peeler
+        .replace(yaml_content, "config.yaml", None, "a: 1\nb: 3")
+        .unwrap();
+    assert!(result.contains(r#"  config.yaml: 'a: 1\nb: 3'"#));
+    assert_eq!(
+        peeler.extract(&result, "config.yaml", None).unwrap(),
+        "a: 1\nb: 3"
+    );
+}
+
+#[test]
+fn test_replace_missing_key() {
+    let peeler = Peeler::new();
+
+    let yaml_content = "kind: ConfigMap\ndata:\n  a.json: '{}'\n";
+    let result = peeler.replace(yaml_content, "b.json", None, "{}");
+    assert!(matches!(result, Err(RspError::KeyNotFound(_))));
+}
+
+#[test]
+fn test_validate_embedded_formats() {
+    assert!(edit::validate("config.json", "{\"a\": 1}").is_ok());
+    assert!(edit::validate("config.yaml", "a: 1\nb: [1, 2]").is_ok());
+    assert!(edit::validate("config.toml", "a = 1").is_ok());
+    assert!(edit::validate("notes.txt", "{ anything").is_ok());
+
+    assert!(matches!(
+        edit::validate("config.json", "{\"a\": 1,}"),
+        Err(RspError::Validation(_))
+    ));
+    assert!(matches!(
+        edit::validate("config.yml", "a: [unclosed"),
+        Err(RspError::Validation(_))
+    ));
+    assert!(matches!(
+        edit::validate("config.toml", "a = "),
+        Err(RspError::Validation(_))
+    ));
+}
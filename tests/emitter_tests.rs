@@ -0,0 +1,116 @@
+use rsp_cli::Peeler;
+use rsp_cli::emitter::Emitter;
+use serde_yaml::Value;
+use std::io::Cursor;
+
+fn emit(value: &Value) -> String {
+    let mut emitter = Emitter::new(Vec::new());
+    emitter.document(value).unwrap();
+    String::from_utf8(emitter.into_inner()).unwrap()
+}
+
+#[test]
+fn test_emitter_nested_collections() {
+    let yaml_content = r#"apiVersion: apps/v1
+kind: Deployment
+spec:
+  replicas: 3
+  paused: false
+  template:
+    spec:
+      containers:
+      - name: app
+        args: ["--port", "8080"]
+        ports:
+        - containerPort: 8080
+      volumes: []
+      nodeSelector: {}
+"#;
+    let value: Value = serde_yaml::from_str(yaml_content).unwrap();
+
+    let output = emit(&value);
+
+    assert_eq!(
+        output,
+        r#"apiVersion: apps/v1
+kind: Deployment
+spec:
+  replicas: 3
+  paused: false
+  template:
+    spec:
+      containers:
+        - name: app
+          args:
+            - --port
+            - "8080"
+          ports:
+            - containerPort: 8080
+      volumes: []
+      nodeSelector: {}
+"#
+    );
+}
+
+#[test]
+fn test_emitter_round_trips_unusual_values() {
+    let yaml_content = r#"
+numbers: [1, -2.5, .inf, 0x10]
+nothing: ~
+1: integer key
+true: boolean key
+? [a, b]
+: sequence key
+? {x: 1}
+: mapping key
+tagged: !Ref MyResource
+tagged_map: !Sub
+  a: 1
+nested:
+  - - inner
+    - "  indented"
+  - |2
+      leading spaces
+    second line
+  - key: |
+      block in sequence
+    other: "quoted: value"
+"#;
+    let value: Value = serde_yaml::from_str(yaml_content).unwrap();
+
+    let output = emit(&value);
+    let reparsed: Value = serde_yaml::from_str(&output).unwrap();
+
+    assert_eq!(reparsed, value, "emitted:\n{output}");
+    assert!(output.contains("  - key: |\n      block in sequence\n"));
+    assert!(output.contains("tagged: !Ref MyResource\n"));
+}
+
+#[test]
+fn test_emitter_quotes_yaml11_booleans_and_dates() {
+    let value: Value = serde_yaml::from_str(
+        "version: \"2010-09-09\"\nenabled: \"on\"\nanswer: \"No\"\nname: nope\n",
+    )
+    .unwrap();
+    assert_eq!(
+        emit(&value),
+        "version: \"2010-09-09\"\nenabled: \"on\"\nanswer: \"No\"\nname: nope\n"
+    );
+}
+
+#[test]
+fn test_peel_reader_streams_to_writer() {
+    let peeler = Peeler::new();
+    let input = Cursor::new(
+        "kind: ConfigMap\ndata:\n  app.yaml: \"a: 1\\nb: 2\"\n---\nkind: Service\nspec:\n  ports:\n  - port: 80\n",
+    );
+    let mut output = Vec::new();
+
+    let report = peeler.peel_reader(input, &mut output).unwrap();
+
+    assert_eq!(report.entries.len(), 1);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "kind: ConfigMap\ndata:\n  app.yaml: |-\n    a: 1\n    b: 2\n---\nkind: Service\nspec:\n  ports:\n    - port: 80\n"
+    );
+}
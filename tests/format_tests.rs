@@ -0,0 +1,166 @@
+use rsp_cli::error::RspError;
+use rsp_cli::format::{EmbeddedFormat, FormatRegistry};
+use rsp_cli::{EntryStatus, Peeler};
+
+/// A format for HCL files whose values are stored with `\n` escapes only.
+struct Hcl;
+
+impl EmbeddedFormat for Hcl {
+    fn name(&self) -> &str {
+        "hcl"
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        key.ends_with(".hcl") || key.ends_with(".tf")
+    }
+
+    fn unescape(&self, raw: &str) -> Result<String, RspError> {
+        Ok(raw.replace("\\n", "\n"))
+    }
+
+    fn escape(&self, content: &str) -> String {
+        content.replace('\n', "\\n")
+    }
+
+    fn validate(&self, content: &str) -> Result<(), RspError> {
+        if content.matches('{').count() == content.matches('}').count() {
+            Ok(())
+        } else {
+            Err(RspError::Validation("unbalanced braces".to_string()))
+        }
+    }
+}
+
+/// Recognizes nginx configuration by its content.
+struct Nginx;
+
+impl EmbeddedFormat for Nginx {
+    fn name(&self) -> &str {
+        "nginx"
+    }
+
+    fn matches_key(&self, key: &str) -> bool {
+        key == "nginx.conf"
+    }
+
+    fn matches_content(&self, content: &str) -> bool {
+        content.trim_start().starts_with("server {")
+    }
+}
+
+#[test]
+fn test_custom_format_is_peeled_and_reported() {
+    let mut peeler = Peeler::new();
+    peeler.register_format(Hcl);
+
+    let yaml_content = r#"kind: ConfigMap
+metadata:
+  name: terraform
+data:
+  main.tf: "resource \"null\" \"x\" {\\n  path = \"C:\\\\tmp\"\\n}"
+  broken.hcl: "a {\\n"
+"#;
+
+    let output = peeler.peel_str(yaml_content).unwrap();
+
+    // The custom unescape keeps the escaped backslash as it is
+    assert!(output.text.contains(
+        "  main.tf: |-\n    resource \"null\" \"x\" {\n      path = \"C:\\\\tmp\"\n    }\n"
+    ));
+    let entries = &output.report.entries;
+    assert_eq!(entries[0].format.as_deref(), Some("hcl"));
+    assert_eq!(entries[0].status, EntryStatus::Peeled);
+    assert_eq!(
+        entries[1].warnings,
+        vec!["invalid HCL in broken.hcl: unbalanced braces".to_string()]
+    );
+
+    let hcl = peeler.formats().get("hcl").unwrap();
+    assert_eq!(hcl.escape("a\nb"), "a\\nb");
+}
+
+#[test]
+fn test_registry_detection_and_precedence() {
+    let mut registry = FormatRegistry::new();
+    assert_eq!(registry.for_key("app.json").unwrap().name(), "json");
+    assert_eq!(registry.for_key("app.yml").unwrap().name(), "yaml");
+    assert!(registry.for_key("site.conf").is_none());
+
+    registry.register(Nginx);
+    assert_eq!(
+        registry
+            .detect("site.conf", "server {\n  listen 80;\n}")
+            .unwrap()
+            .name(),
+        "nginx"
+    );
+    assert!(registry.detect("site.conf", "listen 80;").is_none());
+
+    // A format registered later wins over the built-ins
+    struct LooseJson;
+    impl EmbeddedFormat for LooseJson {
+        fn name(&self) -> &str {
+            "json5"
+        }
+        fn matches_key(&self, key: &str) -> bool {
+            key.ends_with(".json") || key.ends_with(".json5")
+        }
+    }
+    registry.register(LooseJson);
+    assert_eq!(registry.for_key("app.json").unwrap().name(), "json5");
+    assert!(registry.validate("app.json", "{a: 1,}").is_ok());
+
+    assert!(FormatRegistry::empty().for_key("app.json").is_none());
+}
+
+#[test]
+fn test_configmap_keys_are_not_detected_by_content() {
+    let peeler = Peeler::new();
+    let manifest = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: policies
+data:
+  policy: '{"a":"line1\nline2"}'
+"#;
+
+    let output = peeler.peel_str(manifest).unwrap();
+    let document: serde_yaml::Value = serde_yaml::from_str(&output.text).unwrap();
+    assert_eq!(
+        document["data"]["policy"].as_str(),
+        Some(r#"{"a":"line1\nline2"}"#)
+    );
+    assert_eq!(
+        output.report.entries[0].status,
+        EntryStatus::Skipped {
+            reason: "unsupported file type".to_string()
+        }
+    );
+    assert_eq!(
+        peeler.extract(manifest, "policy", None).unwrap(),
+        r#"{"a":"line1\nline2"}"#
+    );
+    assert_eq!(
+        peeler.escape_value("policy", r#"{"a":"line1\nline2"}"#),
+        r#"{\"a\":\"line1\\nline2\"}"#
+    );
+}
+
+#[test]
+fn test_builtin_formats() {
+    let registry = FormatRegistry::new();
+
+    let json = registry.get("json").unwrap();
+    assert_eq!(
+        json.format("{\"b\":1,\"a\":[true]}").unwrap(),
+        "{\n  \"a\": [\n    true\n  ],\n  \"b\": 1\n}\n"
+    );
+    assert_eq!(json.parse("{\"a\":1}").unwrap()["a"], 1);
+
+    let toml = registry.get("toml").unwrap();
+    assert_eq!(toml.parse("a = 1").unwrap()["a"], 1);
+    assert!(matches!(
+        registry.validate("app.toml", "a = "),
+        Err(RspError::Validation(message)) if message.starts_with("invalid TOML in app.toml")
+    ));
+}
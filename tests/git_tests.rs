@@ -0,0 +1,105 @@
+use rsp_cli::git;
+use rsp_cli::peeler::Peeler;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_textconv_peels_configmap() {
+    let input = br#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: example-config
+data:
+  config.json: "{\"hello\":\"world\",\n\"foo\":\"bar\"}"
+"#;
+
+    let output = String::from_utf8(git::textconv(&Peeler::new(), input)).unwrap();
+    assert!(output.contains("config.json: |"));
+    assert!(output.contains("  {\"hello\":\"world\","));
+}
+
+#[test]
+fn test_textconv_peels_other_rule_targets() {
+    let input = br#"apiVersion: argoproj.io/v1alpha1
+kind: Application
+metadata:
+  name: guestbook
+spec:
+  source:
+    helm:
+      values: "replicaCount: 2\nimage:\n  tag: v1\n"
+"#;
+
+    let output = String::from_utf8(git::textconv(&Peeler::new(), input)).unwrap();
+    assert!(
+        output.contains(
+            "      values: |\n        replicaCount: 2\n        image:\n          tag: v1\n"
+        )
+    );
+}
+
+#[test]
+fn test_textconv_falls_back_to_original() {
+    let peeler = Peeler::new();
+
+    let invalid = b"invalid: yaml: content: [unclosed";
+    assert_eq!(git::textconv(&peeler, invalid), invalid.to_vec());
+
+    let deployment = b"kind: Deployment\nspec:\n  replicas: 3\n";
+    assert_eq!(git::textconv(&peeler, deployment), deployment.to_vec());
+
+    let binary = [0xFF, 0xFE, 0xFD, 0xFC];
+    assert_eq!(git::textconv(&peeler, &binary), binary.to_vec());
+}
+
+#[test]
+fn test_git_install_is_idempotent() {
+    let repo = TempDir::new().unwrap();
+    let status = Command::new("git")
+        .args(["init", "-q"])
+        .current_dir(repo.path())
+        .status()
+        .expect("Failed to run git");
+    assert!(status.success());
+    fs::write(repo.path().join(".gitattributes"), "*.png binary").unwrap();
+
+    let manifest_path = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+    let install = || {
+        Command::new("cargo")
+            .args([
+                "run",
+                "--manifest-path",
+                manifest_path,
+                "--",
+                "git",
+                "install",
+            ])
+            .current_dir(repo.path())
+            .output()
+            .expect("Failed to execute command")
+    };
+
+    let output = install();
+    assert!(output.status.success());
+    let output = install();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("already installed"));
+
+    let attributes = fs::read_to_string(repo.path().join(".gitattributes")).unwrap();
+    assert_eq!(
+        attributes,
+        "*.png binary\n*.yaml diff=rsp merge=rsp\n*.yml diff=rsp merge=rsp\n"
+    );
+
+    let config = Command::new("git")
+        .args(["config", "--local", "diff.rsp.textconv"])
+        .current_dir(repo.path())
+        .output()
+        .expect("Failed to run git");
+    assert_eq!(
+        String::from_utf8(config.stdout).unwrap().trim(),
+        "rsp textconv"
+    );
+}
@@ -0,0 +1,174 @@
+use rsp_cli::PeelOptions;
+use rsp_cli::error::RspError;
+use rsp_cli::krm;
+use serde_yaml::Value;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const RESOURCE_LIST: &str = r#"apiVersion: config.kubernetes.io/v1
+kind: ResourceList
+items:
+  - apiVersion: v1
+    kind: ConfigMap
+    metadata:
+      name: app
+      annotations:
+        config.kubernetes.io/index: '0'
+    data:
+      config.json: "{\\\"a\\\":\\n1}"
+      broken.json: "{"
+  - apiVersion: v1
+    kind: ConfigMap
+    metadata:
+      name: dashboards
+      labels:
+        grafana_dashboard: "1"
+    data:
+      nodes.json: '{"title":"Nodes","id":3}'
+"#;
+
+fn results(text: &str) -> Vec<Value> {
+    let list: Value = serde_yaml::from_str(text).unwrap();
+    list.get("results")
+        .and_then(Value::as_sequence)
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_krm_peels_items_and_reports_results() {
+    let output = krm::run(PeelOptions::new(), RESOURCE_LIST).unwrap();
+
+    assert!(!output.errors);
+    assert!(
+        output
+            .text
+            .starts_with("apiVersion: config.kubernetes.io/v1\nkind: ResourceList\nitems:\n")
+    );
+    assert!(
+        output
+            .text
+            .contains("      config.json: |-\n        {\"a\":\n        1}\n")
+    );
+    assert!(
+        output
+            .text
+            .contains("        config.kubernetes.io/index: \"0\"\n")
+    );
+
+    let results = results(&output.text);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["severity"], "warning");
+    assert_eq!(results[0]["resourceRef"]["kind"], "ConfigMap");
+    assert_eq!(results[0]["resourceRef"]["name"], "app");
+    assert_eq!(results[0]["field"]["path"], r#"data["broken.json"]"#);
+}
+
+#[test]
+fn test_krm_function_config() {
+    // Settings in a ConfigMap are read as YAML
+    let input = format!(
+        "{RESOURCE_LIST}functionConfig:\n  apiVersion: v1\n  kind: ConfigMap\n  metadata:\n    name: rsp\n  data:\n    profiles: '{{grafana: true}}'\n    strip_dashboard_fields: 'true'\n    keys: '[nodes.json]'\n"
+    );
+    let output = krm::run(PeelOptions::new(), &input).unwrap();
+    assert!(
+        output.text.contains(
+            "      nodes.json: |\n        {\n          \"title\": \"Nodes\"\n        }\n"
+        )
+    );
+    assert!(
+        output
+            .text
+            .contains(r#"      config.json: "{\\\"a\\\":\\n1}""#)
+    );
+
+    // Typed settings in a spec, and unknown fields reported as errors
+    let input = format!(
+        "{RESOURCE_LIST}functionConfig:\n  apiVersion: rsp.dev/v1alpha1\n  kind: PeelConfig\n  spec:\n    unescape: false\n    profile: grafana\n"
+    );
+    let output = krm::run(PeelOptions::new(), &input).unwrap();
+    assert!(output.errors);
+    let results = results(&output.text);
+    assert_eq!(results[0]["severity"], "error");
+    assert!(
+        results[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("unknown field `profile`")
+    );
+    // Items are left alone when the settings are broken
+    assert!(
+        output
+            .text
+            .contains(r#"      config.json: "{\\\"a\\\":\\n1}""#)
+    );
+
+    assert!(matches!(
+        krm::run(PeelOptions::new(), "kind: ConfigMap\n"),
+        Err(RspError::InvalidFormat(_))
+    ));
+}
+
+#[test]
+fn test_krm_pack_mode_reverses_peel() {
+    let peeled = krm::run(PeelOptions::new(), RESOURCE_LIST).unwrap().text;
+    let peeled_list: Value = serde_yaml::from_str(&peeled).unwrap();
+
+    let input = format!("{peeled}functionConfig:\n  kind: PeelConfig\n  spec:\n    mode: pack\n")
+        .replace("results:\n", "previous:\n");
+    let packed = krm::run(PeelOptions::new(), &input).unwrap();
+    let packed_list: Value = serde_yaml::from_str(&packed.text).unwrap();
+    let original: Value = serde_yaml::from_str(RESOURCE_LIST).unwrap();
+
+    assert_eq!(
+        packed_list["items"][0]["data"],
+        original["items"][0]["data"]
+    );
+    assert_ne!(
+        peeled_list["items"][0]["data"],
+        original["items"][0]["data"]
+    );
+}
+
+#[test]
+fn test_cli_krm() {
+    let mut child = Command::new("cargo")
+        .args(["run", "--quiet", "--", "krm"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(RESOURCE_LIST.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("kind: ResourceList\n"));
+    assert!(stdout.contains("      config.json: |-\n"));
+
+    // Items that cannot be processed make the function fail
+    let mut child = Command::new("cargo")
+        .args(["run", "--quiet", "--", "krm"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"apiVersion: config.kubernetes.io/v1\nkind: ResourceList\nitems:\n  - just a string\n",
+        )
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("    severity: error\n"));
+}
@@ -0,0 +1,255 @@
+use rsp_cli::kustomize::{self, ExtractedLiteral};
+use rsp_cli::{PeelOptions, Peeler, Profile};
+use serde_yaml::Value;
+use std::process::Command;
+
+const KUSTOMIZATION: &str = r#"apiVersion: kustomize.config.k8s.io/v1beta1
+kind: Kustomization
+configMapGenerator:
+  - name: app
+    literals:
+      - LOG_LEVEL=debug
+      - "config.json={\n  \"replicas\": 2\n}"
+      - 'settings.yaml="a: 1\nb: 2"'
+secretGenerator:
+  - name: creds
+    files:
+      - token.txt
+    literals:
+      - "ca.pem=-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n"
+"#;
+
+#[test]
+fn test_kustomize_profile_peels_literal_values() {
+    let output = Peeler::new().peel_str(KUSTOMIZATION).unwrap();
+
+    assert!(output.text.contains(
+        "    literals:\n      - LOG_LEVEL=debug\n      - |-\n        config.json={\n          \"replicas\": 2\n        }\n"
+    ));
+    // The quotes kustomize strips stay around the peeled value
+    assert!(
+        output
+            .text
+            .contains("      - |-\n        settings.yaml=\"a: 1\n        b: 2\"\n")
+    );
+
+    let entries = &output.report.entries;
+    let summary: Vec<(&str, &str, Option<&str>)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.path.as_str(),
+                entry.key.as_str(),
+                entry.format.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("configMapGenerator[0].literals[0]", "LOG_LEVEL", None),
+            (
+                "configMapGenerator[0].literals[1]",
+                "config.json",
+                Some("json")
+            ),
+            (
+                "configMapGenerator[0].literals[2]",
+                "settings.yaml",
+                Some("yaml")
+            ),
+            ("secretGenerator[0].literals[0]", "ca.pem", None),
+        ]
+    );
+}
+
+#[test]
+fn test_kustomize_profile_packs_and_can_be_turned_off() {
+    let peeler = Peeler::new();
+    let mut document: Value = serde_yaml::from_str(KUSTOMIZATION).unwrap();
+    peeler.peel_value(&mut document).unwrap();
+    peeler.pack_value(&mut document).unwrap();
+    assert_eq!(
+        document["configMapGenerator"][0]["literals"][2],
+        Value::from("settings.yaml=\"a: 1\\nb: 2\"")
+    );
+
+    let options = PeelOptions::new().profile(Profile::Kustomize, false);
+    let output = Peeler::with_options(options)
+        .peel_str(KUSTOMIZATION)
+        .unwrap();
+    assert!(output.report.entries.is_empty());
+}
+
+#[test]
+fn test_extract_literals_moves_long_values_to_files() {
+    let mut kustomization: Value = serde_yaml::from_str(KUSTOMIZATION).unwrap();
+    let extracted = kustomize::extract_literals(&mut kustomization, 80).unwrap();
+
+    assert_eq!(
+        extracted,
+        vec![
+            ExtractedLiteral {
+                generator: "app".to_string(),
+                key: "config.json".to_string(),
+                path: "app/config.json".to_string(),
+                content: "{\n  \"replicas\": 2\n}".to_string(),
+            },
+            ExtractedLiteral {
+                generator: "creds".to_string(),
+                key: "ca.pem".to_string(),
+                path: "creds/ca.pem".to_string(),
+                content: "-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n"
+                    .to_string(),
+            },
+        ]
+    );
+
+    let app = &kustomization["configMapGenerator"][0];
+    assert_eq!(
+        app["literals"],
+        serde_yaml::from_str::<Value>("[LOG_LEVEL=debug, 'settings.yaml=\"a: 1\\nb: 2\"']")
+            .unwrap()
+    );
+    assert_eq!(
+        app["files"],
+        serde_yaml::from_str::<Value>("[app/config.json]").unwrap()
+    );
+    let creds = &kustomization["secretGenerator"][0];
+    assert!(creds.get("literals").is_none());
+    assert_eq!(
+        creds["files"],
+        serde_yaml::from_str::<Value>("[token.txt, creds/ca.pem]").unwrap()
+    );
+}
+
+#[test]
+fn test_extract_literals_strips_quotes_and_rejects_clashes() {
+    let mut kustomization: Value = serde_yaml::from_str(
+        "configMapGenerator:\n  - name: app\n    literals:\n      - 'note.txt=\"short\"'\n      - ../escape=\"outside\"\n",
+    )
+    .unwrap();
+    let extracted = kustomize::extract_literals(&mut kustomization, 0).unwrap();
+    // kustomize strips the quotes, and keys that are no file names stay
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].content, "short");
+    assert_eq!(
+        kustomization["configMapGenerator"][0]["literals"],
+        serde_yaml::from_str::<Value>("['../escape=\"outside\"']").unwrap()
+    );
+
+    let mut kustomization: Value = serde_yaml::from_str(
+        "configMapGenerator:\n  - name: app\n    literals: [a.txt=one]\nsecretGenerator:\n  - name: app\n    literals: [a.txt=two]\n",
+    )
+    .unwrap();
+    assert!(kustomize::extract_literals(&mut kustomization, 0).is_err());
+}
+
+#[test]
+fn test_extract_literals_in_place_keeps_comments() {
+    let content = r#"# Generated config for the demo app
+apiVersion: kustomize.config.k8s.io/v1beta1
+kind: Kustomization
+configMapGenerator:
+  - name: app  # main app
+    literals:
+    - LOG_LEVEL=debug  # noisy
+    - |-
+      motd.txt=Welcome
+      to the cluster
+    # settings below are reviewed weekly
+    - MODE=batch
+    options:
+      disableNameSuffixHash: true
+secretGenerator:
+  - name: creds
+    files:
+      - token.txt  # rotated by CI
+    literals:
+      - "ca.pem=-----BEGIN CERTIFICATE-----\nMIIB\n-----END CERTIFICATE-----\n"
+  - name: keys
+    literals:
+      - "id_rsa=line one\nline two"
+"#;
+
+    let (updated, extracted) = kustomize::extract_literals_in_place(content, 80).unwrap();
+    assert_eq!(extracted.len(), 3);
+    assert_eq!(
+        updated,
+        r#"# Generated config for the demo app
+apiVersion: kustomize.config.k8s.io/v1beta1
+kind: Kustomization
+configMapGenerator:
+  - name: app  # main app
+    literals:
+    - LOG_LEVEL=debug  # noisy
+    # settings below are reviewed weekly
+    - MODE=batch
+    files:
+      - app/motd.txt
+    options:
+      disableNameSuffixHash: true
+secretGenerator:
+  - name: creds
+    files:
+      - token.txt  # rotated by CI
+      - creds/ca.pem
+  - name: keys
+    files:
+      - keys/id_rsa
+"#
+    );
+
+    let (unchanged, extracted) = kustomize::extract_literals_in_place(&updated, 80).unwrap();
+    assert!(extracted.is_empty());
+    assert_eq!(unchanged, updated);
+}
+
+#[test]
+fn test_cli_extract_literals() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("kustomization.yaml");
+    std::fs::write(&file, KUSTOMIZATION).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "extract-literals",
+            dir.path().to_str().unwrap(),
+            "--dry-run",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Would write"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), KUSTOMIZATION);
+    assert!(!dir.path().join("app").exists());
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "extract-literals", file.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("app/config.json")).unwrap(),
+        "{\n  \"replicas\": 2\n}"
+    );
+    let updated = std::fs::read_to_string(&file).unwrap();
+    assert!(updated.contains("    files:\n      - app/config.json\n"));
+    assert!(!updated.contains("ca.pem="));
+    assert!(updated.contains("      - LOG_LEVEL=debug\n"));
+
+    // A second run finds nothing left to move
+    let output = Command::new("cargo")
+        .args(["run", "--", "extract-literals", file.to_str().unwrap()])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .contains("No literals to extract.")
+    );
+}
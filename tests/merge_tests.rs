@@ -0,0 +1,158 @@
+use rsp_cli::merge::merge_manifests;
+use rsp_cli::peeler::Peeler;
+
+const BASE: &str = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+  labels:
+    app: demo
+data:
+  config.json: "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}"
+"#;
+
+#[test]
+fn test_merge_non_overlapping_changes_in_embedded_file() {
+    let ours = BASE.replace(r#"\"a\": 1"#, r#"\"a\": 10"#);
+    let theirs = BASE.replace(r#"\"c\": 3"#, r#"\"c\": 30"#);
+
+    let outcome = merge_manifests(&Peeler::new(), BASE, &ours, &theirs, 7);
+
+    assert!(!outcome.conflicts);
+    assert_eq!(
+        outcome.text,
+        BASE.replace(r#"\"a\": 1"#, r#"\"a\": 10"#)
+            .replace(r#"\"c\": 3"#, r#"\"c\": 30"#)
+    );
+}
+
+#[test]
+fn test_merge_embedded_files_in_sequences() {
+    // The workload's env values and args are embedded files too, in block
+    // and flow sequences
+    let base = format!(
+        r#"{BASE}---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: app
+spec:
+  template:
+    spec:
+      containers:
+        - name: app
+          args: ["-c", "echo hi"]
+          env:
+            - name: SETTINGS
+              value: "{{\"x\":1}}"
+"#
+    );
+    let ours = base.replace(r#"\"a\": 1"#, r#"\"a\": 10"#);
+    let theirs = base
+        .replace(r#"\"c\": 3"#, r#"\"c\": 30"#)
+        .replace(r#"\"x\":1"#, r#"\"x\":2"#);
+
+    let outcome = merge_manifests(&Peeler::new(), &base, &ours, &theirs, 7);
+
+    assert!(!outcome.conflicts);
+    assert_eq!(
+        outcome.text,
+        base.replace(r#"\"a\": 1"#, r#"\"a\": 10"#)
+            .replace(r#"\"c\": 3"#, r#"\"c\": 30"#)
+            .replace(r#"\"x\":1"#, r#"\"x\":2"#)
+    );
+}
+
+#[test]
+fn test_merge_keeps_our_style_and_their_manifest_changes() {
+    // Our branch peeled the file into a block scalar, theirs changed a label
+    let ours = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app-config
+  labels:
+    app: demo
+data:
+  config.json: |-
+    {
+      "a": 1,
+      "b": 2,
+      "c": 3
+    }
+"#;
+    let theirs = BASE
+        .replace("app: demo", "app: demo-v2")
+        .replace(r#"\"b\": 2"#, r#"\"b\": 20"#);
+
+    let outcome = merge_manifests(&Peeler::new(), BASE, ours, &theirs, 7);
+
+    assert!(!outcome.conflicts);
+    assert!(outcome.text.contains("    app: demo-v2\n"));
+    assert!(outcome.text.contains(
+        "  config.json: |-\n    {\n      \"a\": 1,\n      \"b\": 20,\n      \"c\": 3\n    }\n"
+    ));
+}
+
+#[test]
+fn test_merge_conflict_markers_inside_peeled_content() {
+    let ours = BASE.replace(r#"\"b\": 2"#, r#"\"b\": 20"#);
+    let theirs = BASE.replace(r#"\"b\": 2"#, r#"\"b\": 200"#);
+
+    let outcome = merge_manifests(&Peeler::new(), BASE, &ours, &theirs, 7);
+
+    assert!(outcome.conflicts);
+    assert!(
+        outcome
+            .text
+            .starts_with("apiVersion: v1\nkind: ConfigMap\n")
+    );
+    assert!(
+        outcome
+            .text
+            .contains("  config.json: |-\n    {\n      \"a\": 1,\n")
+    );
+    assert!(
+        outcome
+            .text
+            .contains("    <<<<<<< ours\n      \"b\": 20,\n")
+    );
+    assert!(
+        outcome
+            .text
+            .contains("      \"b\": 200,\n    >>>>>>> theirs\n")
+    );
+    assert!(outcome.text.contains("      \"c\": 3\n    }\n"));
+}
+
+#[test]
+fn test_merge_falls_back_to_line_merge() {
+    let base = "a: [unclosed\nb: 1\n";
+    let ours = "a: [unclosed\nb: 2\n";
+    let theirs = "a: [unclosed\nb: 1\n";
+
+    let outcome = merge_manifests(&Peeler::new(), base, ours, theirs, 7);
+
+    assert!(!outcome.conflicts);
+    assert_eq!(outcome.text, ours);
+}
+
+#[test]
+fn test_merge_modify_delete_is_a_conflict() {
+    let ours = BASE.replace(
+        "  config.json: \"{\\n  \\\"a\\\": 1,\\n  \\\"b\\\": 2,\\n  \\\"c\\\": 3\\n}\"\n",
+        "  other.txt: kept\n",
+    );
+    assert_ne!(ours, BASE);
+    let theirs = BASE.replace(r#"\"a\": 1"#, r#"\"a\": 10"#);
+
+    for (ours, theirs) in [
+        (ours.as_str(), theirs.as_str()),
+        (theirs.as_str(), ours.as_str()),
+    ] {
+        let outcome = merge_manifests(&Peeler::new(), BASE, ours, theirs, 7);
+        assert!(outcome.conflicts);
+        assert!(outcome.text.contains("<<<<<<<"));
+        // The change to the deleted file is not lost
+        assert!(outcome.text.contains(r#"\"a\": 10"#));
+    }
+}
@@ -1,5 +1,7 @@
 use rsp_cli::error::RspError;
+use rsp_cli::options::PeelOptions;
 use rsp_cli::peeler::Peeler;
+use rsp_cli::report::{EntryStatus, ReportEntry};
 use serde_yaml::Value;
 use std::fs;
 use tempfile::NamedTempFile;
@@ -159,14 +161,12 @@ data:
     peeler.process_yaml_value(&mut yaml_value).unwrap();
 
     // Verify that Secret data is not processed (only ConfigMap should be processed)
-    if let Value::Mapping(map) = &yaml_value {
-        if let Some(Value::Mapping(data)) = map.get(Value::String("data".to_string())) {
-            if let Some(Value::String(json_content)) =
-                data.get(Value::String("config.json".to_string()))
-            {
-                assert_eq!(json_content, "{\"hello\":\"world\"}"); // Should remain escaped
-            }
-        }
+    if let Value::Mapping(map) = &yaml_value
+        && let Some(Value::Mapping(data)) = map.get(Value::String("data".to_string()))
+        && let Some(Value::String(json_content)) =
+            data.get(Value::String("config.json".to_string()))
+    {
+        assert_eq!(json_content, "{\"hello\":\"world\"}"); // Should remain escaped
     }
 }
 
@@ -199,6 +199,195 @@ data:
     assert!(!result.contains("simple: |"));
 }
 
+#[test]
+fn test_extract_from_multi_document_file() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: first
+data:
+  config.json: "{\"first\":true}"
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: first
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: second
+data:
+  config.json: "{\n  \"second\": true\n}"
+  app.yaml: "a: 1\\nb: 2"
+---
+"#;
+
+    // Key present in a single ConfigMap
+    let result = peeler.extract(yaml_content, "app.yaml", None).unwrap();
+    assert_eq!(result, "a: 1\nb: 2");
+
+    // Select between ConfigMaps by name
+    let result = peeler
+        .extract(yaml_content, "config.json", Some("second"))
+        .unwrap();
+    assert_eq!(result, "{\n  \"second\": true\n}");
+
+    // Ambiguous without a name
+    let result = peeler.extract(yaml_content, "config.json", None);
+    assert!(matches!(result, Err(RspError::Processing(_))));
+
+    // Missing key and missing ConfigMap
+    let result = peeler.extract(yaml_content, "missing.json", Some("first"));
+    assert!(matches!(result, Err(RspError::KeyNotFound(_))));
+    let result = peeler.extract(yaml_content, "config.json", Some("third"));
+    assert!(matches!(result, Err(RspError::ResourceNotFound(_))));
+}
+
+#[test]
+fn test_peel_str_returns_text_and_report() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: first
+  namespace: prod
+data:
+  config.json: "{\"hello\":\"world\",\n\"foo\":\"bar\"}"
+  notes.txt: plain
+---
+apiVersion: v1
+kind: Service
+metadata:
+  name: first
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: second
+data:
+  app.yaml: "a: 1\nb: 2"
+"#;
+
+    let output = peeler.peel_str(yaml_content).unwrap();
+
+    assert!(
+        output
+            .text
+            .contains("  config.json: |-\n    {\"hello\":\"world\",\n")
+    );
+    assert!(
+        output
+            .text
+            .contains("\n---\napiVersion: v1\nkind: Service\n")
+    );
+    assert!(output.text.contains("  app.yaml: |-\n    a: 1\n    b: 2\n"));
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[0],
+        ReportEntry {
+            document: 0,
+            kind: Some("ConfigMap".to_string()),
+            namespace: Some("prod".to_string()),
+            name: Some("first".to_string()),
+            resource: "ConfigMap/prod/first".to_string(),
+            path: "data[\"config.json\"]".to_string(),
+            key: "config.json".to_string(),
+            format: Some("json".to_string()),
+            status: EntryStatus::Peeled,
+            bytes_before: 30,
+            bytes_after: 30,
+            warnings: Vec::new(),
+        }
+    );
+    assert_eq!(entries[1].key, "notes.txt");
+    assert_eq!(
+        entries[1].status,
+        EntryStatus::Skipped {
+            reason: "unsupported file type".to_string()
+        }
+    );
+    assert_eq!(entries[2].document, 2);
+    assert_eq!(entries[2].resource, "ConfigMap/second");
+    assert_eq!(entries[2].format.as_deref(), Some("yaml"));
+    assert_eq!(output.report.peeled().count(), 2);
+}
+
+#[test]
+fn test_peel_report_sizes_and_warnings() {
+    let peeler = Peeler::new();
+
+    let yaml_content = r#"kind: ConfigMap
+metadata:
+  name: app
+data:
+  broken.json: "{\"a\": 1,\\n"
+binaryData:
+  logo.png: iVBORw0KGgo=
+"#;
+
+    let report = peeler.peel_str(yaml_content).unwrap().report;
+
+    let broken = &report.entries[0];
+    assert_eq!(broken.status, EntryStatus::Peeled);
+    assert_eq!(broken.bytes_before, 10);
+    assert_eq!(broken.bytes_after, 9);
+    assert_eq!(broken.warnings.len(), 1);
+    assert!(broken.warnings[0].starts_with("invalid JSON in broken.json"));
+
+    let binary = &report.entries[1];
+    assert_eq!(binary.key, "logo.png");
+    assert_eq!(
+        binary.status,
+        EntryStatus::Skipped {
+            reason: "binary data".to_string()
+        }
+    );
+
+    let summary = report.to_string();
+    assert!(
+        summary.contains("peeled   ConfigMap/app data[\"broken.json\"] (json, 10 -> 9 bytes)\n")
+    );
+    assert!(summary.ends_with("1 peeled, 1 skipped, 0 failed\n"));
+}
+
+#[test]
+fn test_peel_str_with_options() {
+    let yaml_content = r#"kind: ConfigMap
+data:
+  config.json: "{\\\"a\\\": 1}"
+  app.yaml: "a: 1\\nb: 2"
+"#;
+
+    // Only the selected key is peeled
+    let peeler = Peeler::with_options(PeelOptions::new().key("app.yaml"));
+    let output = peeler.peel_str(yaml_content).unwrap();
+    assert_eq!(output.report.peeled().count(), 1);
+    assert_eq!(output.report.skipped().count(), 1);
+    assert!(output.text.contains("  app.yaml: |-\n    a: 1\n    b: 2\n"));
+    assert!(output.text.contains(r#"  config.json: "{\\\"a\\\": 1}""#));
+
+    // Without unescaping, backslash sequences are kept as they are
+    let peeler = Peeler::with_options(PeelOptions::new().unescape(false));
+    let output = peeler.peel_str(yaml_content).unwrap();
+    assert!(output.text.contains(r#"  app.yaml: "a: 1\\nb: 2""#));
+}
+
+#[test]
+fn test_peel_str_empty_input() {
+    let peeler = Peeler::new();
+
+    let result = peeler.peel_str("");
+    assert!(matches!(result, Err(RspError::InvalidFormat(_))));
+
+    let result = peeler.peel_str("- just\n- a list\n");
+    assert!(matches!(result, Err(RspError::InvalidFormat(_))));
+}
+
 #[cfg(test)]
 mod file_tests {
     use super::*;
@@ -0,0 +1,191 @@
+use rsp_cli::{EntryStatus, PeelOptions, Peeler, Profile};
+use std::process::Command;
+
+const TEKTON: &str = r##"apiVersion: tekton.dev/v1
+kind: Task
+metadata:
+  name: build
+spec:
+  steps:
+    - name: compile
+      image: golang
+      script: "#!/usr/bin/env bash\nset -euo pipefail\nprintf '%s\\n' \"$(params.target)\"\ngo build ./...\n"
+    - name: escaped
+      image: python
+      script: '#!/usr/bin/env python3\nprint("hello")\n'
+    - name: short
+      image: alpine
+      script: echo done
+    - name: printf
+      image: alpine
+      script: "printf '%s\\n' x"
+    - name: tabs
+      image: bash
+      script: echo -e 'a\tb'
+---
+apiVersion: tekton.dev/v1beta1
+kind: Pipeline
+metadata:
+  name: ci
+spec:
+  tasks:
+    - name: inline
+      taskSpec:
+        steps:
+          - image: alpine
+            script: "#!/bin/sh\necho inline\n"
+"##;
+
+const WORKFLOW: &str = r##"apiVersion: argoproj.io/v1alpha1
+kind: CronWorkflow
+metadata:
+  name: report
+spec:
+  workflowSpec:
+    templates:
+      - name: gen
+        script:
+          image: python:3.12
+          command: [python]
+          source: "#!/usr/bin/env python3\nimport json\nprint(json.dumps({\"ok\": True}))\n"
+      - name: steps
+        steps:
+          - - name: run
+              template: gen
+"##;
+
+#[test]
+fn test_tekton_scripts_keep_their_shebang() {
+    let output = Peeler::new().peel_str(TEKTON).unwrap();
+    assert!(output.text.contains(
+        "      script: |\n        #!/usr/bin/env bash\n        set -euo pipefail\n        printf '%s\\n' \"$(params.target)\"\n        go build ./...\n"
+    ));
+    // A script escaped onto one line after its shebang is unescaped
+    assert!(
+        output.text.contains(
+            "      script: |\n        #!/usr/bin/env python3\n        print(\"hello\")\n"
+        )
+    );
+    assert!(output.text.contains("      script: echo done\n"));
+    assert!(
+        output.text.contains(
+            "            script: |\n              #!/bin/sh\n              echo inline\n"
+        )
+    );
+
+    let statuses: Vec<(&str, &EntryStatus)> = output
+        .report
+        .entries
+        .iter()
+        .map(|entry| (entry.path.as_str(), &entry.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("spec.steps[0].script", &EntryStatus::Peeled),
+            ("spec.steps[1].script", &EntryStatus::Peeled),
+            (
+                "spec.steps[2].script",
+                &EntryStatus::Skipped {
+                    reason: "single-line text".to_string()
+                }
+            ),
+            (
+                "spec.steps[3].script",
+                &EntryStatus::Skipped {
+                    reason: "single-line text".to_string()
+                }
+            ),
+            (
+                "spec.steps[4].script",
+                &EntryStatus::Skipped {
+                    reason: "single-line text".to_string()
+                }
+            ),
+            (
+                "spec.tasks[0].taskSpec.steps[0].script",
+                &EntryStatus::Peeled
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_one_line_scripts_keep_their_escapes() {
+    let mut document: serde_yaml::Value =
+        serde_yaml::from_str(TEKTON.split("---").next().unwrap()).unwrap();
+    Peeler::new().peel_value(&mut document).unwrap();
+    let steps = &document["spec"]["steps"];
+    assert_eq!(steps[3]["script"].as_str(), Some(r"printf '%s\n' x"));
+    assert_eq!(steps[4]["script"].as_str(), Some(r"echo -e 'a\tb'"));
+
+    let output = Peeler::with_options(PeelOptions::new().template(true))
+        .peel_str(TEKTON)
+        .unwrap();
+    assert!(
+        output
+            .text
+            .contains("      script: \"printf '%s\\\\n' x\"\n")
+    );
+    assert!(output.text.contains("      script: echo -e 'a\\tb'\n"));
+}
+
+#[test]
+fn test_argo_workflow_script_sources() {
+    let output = Peeler::new().peel_str(WORKFLOW).unwrap();
+    assert!(output.text.contains(
+        "          source: |\n            #!/usr/bin/env python3\n            import json\n            print(json.dumps({\"ok\": True}))\n"
+    ));
+    assert_eq!(output.report.entries.len(), 1);
+    assert_eq!(
+        output.report.entries[0].path,
+        "spec.workflowSpec.templates[0].script.source"
+    );
+
+    let options = PeelOptions::new().profile(Profile::ArgoWorkflows, false);
+    let output = Peeler::with_options(options).peel_str(WORKFLOW).unwrap();
+    assert!(output.report.entries.is_empty());
+}
+
+#[test]
+fn test_cli_pipeline_profiles() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("task.yaml");
+    std::fs::write(&input, TEKTON).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--no-profile",
+            "tekton",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r##"      script: "#!/usr/bin/env python3\\nprint(\"hello\")\\n""##));
+
+    let config = dir.path().join(".rsp.toml");
+    std::fs::write(&config, "[profiles]\nargo-workflows = false\n").unwrap();
+    let workflow = dir.path().join("workflow.yaml");
+    std::fs::write(&workflow, WORKFLOW).unwrap();
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            workflow.to_str().unwrap(),
+            "--config",
+            config.to_str().unwrap(),
+            "--report",
+            "human",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("0 peeled, 0 skipped, 0 failed"));
+}
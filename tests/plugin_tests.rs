@@ -0,0 +1,180 @@
+use rsp_cli::Peeler;
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::PermissionsExt;
+use std::process::{Command, Stdio};
+
+const KUBECTL_LIST: &str = r#"apiVersion: v1
+kind: List
+items:
+  - apiVersion: v1
+    kind: ConfigMap
+    metadata:
+      name: app
+      namespace: prod
+    data:
+      config.json: "{\"a\":\\n1}"
+  - apiVersion: v1
+    kind: ConfigMap
+    metadata:
+      name: web
+      namespace: prod
+    data:
+      app.yaml: "b: 1\\nc: 2"
+metadata:
+  resourceVersion: ""
+"#;
+
+#[test]
+fn test_peel_list_items() {
+    let output = Peeler::new().peel_str(KUBECTL_LIST).unwrap();
+
+    assert!(
+        output
+            .text
+            .contains("    data:\n      config.json: |-\n        {\"a\":\n        1}\n")
+    );
+    assert!(
+        output
+            .text
+            .contains("    data:\n      app.yaml: |-\n        b: 1\n        c: 2\n")
+    );
+
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].resource, "ConfigMap/prod/app");
+    assert_eq!(entries[0].path, r#"items[0].data["config.json"]"#);
+    assert_eq!(entries[1].path, r#"items[1].data["app.yaml"]"#);
+    assert!(entries.iter().all(|entry| entry.document == 0));
+}
+
+#[test]
+fn test_plugin_peels_stdin_like_rsp_peel() {
+    let run = |binary: &str, args: &[&str]| {
+        let mut child = Command::new("cargo")
+            .args(["run", "--quiet", "--bin", binary, "--"])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("Failed to execute command");
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(KUBECTL_LIST.as_bytes())
+            .unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    let plugin = run("kubectl-rsp", &["--key", "app.yaml", "--report", "human"]);
+    let rsp = run("rsp", &["peel", "--key", "app.yaml", "--report", "human"]);
+    assert_eq!(plugin, rsp);
+    assert!(plugin.1.contains("1 peeled, 1 skipped, 0 failed"));
+}
+
+#[test]
+fn test_plugin_get_runs_kubectl() {
+    let dir = tempfile::tempdir().unwrap();
+    let list = dir.path().join("list.yaml");
+    let args = dir.path().join("args");
+    fs::write(&list, KUBECTL_LIST).unwrap();
+    let kubectl = dir.path().join("kubectl");
+    fs::write(
+        &kubectl,
+        format!(
+            "#!/bin/sh\necho \"$@\" > '{}'\ncat '{}'\n",
+            args.display(),
+            list.display()
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&kubectl, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--bin",
+            "kubectl-rsp",
+            "--",
+            "get",
+            "cm",
+            "app",
+            "web",
+            "-n",
+            "prod",
+            "--context",
+            "staging",
+            "-o",
+            "yaml",
+        ])
+        .env("KUBECTL", &kubectl)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("      config.json: |-\n        {\"a\":\n        1}\n"));
+    assert_eq!(
+        fs::read_to_string(&args).unwrap(),
+        "get cm app web --namespace=prod --context=staging -o yaml\n"
+    );
+
+    // `-o json` prints JSON, and `-k` is no short form of `--key`
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--bin",
+            "kubectl-rsp",
+            "--",
+            "get",
+            "cm",
+            "-o",
+            "json",
+        ])
+        .env("KUBECTL", &kubectl)
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.trim_start().starts_with('{'));
+    assert_eq!(fs::read_to_string(&args).unwrap(), "get cm -o yaml\n");
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--bin",
+            "kubectl-rsp",
+            "--",
+            "get",
+            "cm",
+            "-k",
+            "config.json",
+        ])
+        .env("KUBECTL", &kubectl)
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+
+    // A failing kubectl fails the plugin
+    fs::write(&kubectl, "#!/bin/sh\necho 'not found' >&2\nexit 1\n").unwrap();
+    let output = Command::new("cargo")
+        .args(["run", "--quiet", "--bin", "kubectl-rsp", "--", "get", "cm"])
+        .env("KUBECTL", &kubectl)
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("not found")
+    );
+}
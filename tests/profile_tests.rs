@@ -0,0 +1,412 @@
+use rsp_cli::config::Config;
+use rsp_cli::{EntryStatus, PeelOptions, Peeler, Profile};
+use serde_yaml::Value;
+use std::process::Command;
+
+const GITOPS: &str = r#"apiVersion: argoproj.io/v1alpha1
+kind: Application
+metadata:
+  name: web
+spec:
+  source:
+    helm:
+      values: "replicaCount: 2\nimage:\n    repository: web   # pinned\n    tag: \"1.2\"\n"
+---
+apiVersion: argoproj.io/v1alpha1
+kind: ApplicationSet
+metadata:
+  name: cluster-addons
+spec:
+  template:
+    spec:
+      sources:
+        - repoURL: https://charts.example.com
+          helm:
+            values: "a: 1\nb: 2"
+---
+apiVersion: helm.toolkit.fluxcd.io/v2
+kind: HelmRelease
+metadata:
+  name: api
+spec:
+  values:
+    image: "api\\nv2"
+---
+apiVersion: helm.toolkit.fluxcd.io/v2
+kind: HelmRelease
+metadata:
+  name: worker
+spec:
+  values: "queue: jobs\nreplicas: 3"
+"#;
+
+#[test]
+fn test_builtin_profiles_peel_helm_values() {
+    let output = Peeler::new().peel_str(GITOPS).unwrap();
+
+    assert!(output.text.contains(
+        "      values: |\n        replicaCount: 2\n        image:\n            repository: web   # pinned\n            tag: \"1.2\"\n"
+    ));
+    assert!(output.text.contains(
+        "        - repoURL: https://charts.example.com\n          helm:\n            values: |-\n              a: 1\n              b: 2\n"
+    ));
+    assert!(
+        output
+            .text
+            .contains("  values: |-\n    queue: jobs\n    replicas: 3\n")
+    );
+    // Values given as a mapping are the values themselves, not files
+    assert!(output.text.contains("    image: api\\nv2\n"));
+
+    let paths: Vec<&str> = output
+        .report
+        .entries
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "spec.source.helm.values",
+            "spec.template.spec.sources[0].helm.values",
+            "spec.values"
+        ]
+    );
+    assert!(
+        output
+            .report
+            .entries
+            .iter()
+            .all(|entry| entry.format.as_deref() == Some("yaml"))
+    );
+}
+
+#[test]
+fn test_profiles_can_be_turned_off() {
+    let options = PeelOptions::new()
+        .profile(Profile::ArgoCd, false)
+        .profile(Profile::Flux, false);
+    let output = Peeler::with_options(options).peel_str(GITOPS).unwrap();
+    assert!(output.report.entries.is_empty());
+
+    let config = Config::from_toml("[profiles]\nargocd = false\n").unwrap();
+    let output = Peeler::with_options(config.apply(PeelOptions::new()))
+        .peel_str(GITOPS)
+        .unwrap();
+    assert_eq!(output.report.entries.len(), 1);
+    assert_eq!(output.report.entries[0].resource, "HelmRelease/worker");
+
+    assert!(Config::from_toml("[profiles]\nspinnaker = true\n").is_err());
+    assert_eq!("flux".parse::<Profile>().unwrap(), Profile::Flux);
+    assert!("spinnaker".parse::<Profile>().is_err());
+}
+
+#[test]
+fn test_normalize_rewrites_inner_yaml() {
+    let options = PeelOptions::new().normalize(true);
+    let output = Peeler::with_options(options).peel_str(GITOPS).unwrap();
+    assert!(output.text.contains(
+        "      values: |\n        replicaCount: 2\n        image:\n          repository: web\n          tag: \"1.2\"\n"
+    ));
+    assert!(
+        output
+            .text
+            .contains("  values: |\n    queue: jobs\n    replicas: 3\n")
+    );
+}
+
+#[test]
+fn test_cli_profiles_and_normalize() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("gitops.yaml");
+    std::fs::write(&input, GITOPS).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--no-profile",
+            "flux",
+            "--normalize",
+            "--report",
+            "json",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("          repository: web\n"));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("\"resource\": \"Application/web\""));
+    assert!(!stderr.contains("HelmRelease"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--profile",
+            "spinnaker",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
+
+const DASHBOARDS: &str = r#"apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: dashboards
+  labels:
+    grafana_dashboard: "1"
+data:
+  nodes.json: '{"title":"Nodes","id":42,"version":7,"iteration":1690000000,"panels":[{"type":"graph","id":2}]}'
+  broken.json: '{"panels":{}}'
+  README.md: 'Dashboards owned by the platform team.'
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: plain
+data:
+  nodes.json: '{"title":"Nodes","id":42}'
+"#;
+
+#[test]
+fn test_grafana_profile_pretty_prints_dashboards() {
+    // Off by default, so dashboards are only unescaped as JSON
+    let output = Peeler::new().peel_str(DASHBOARDS).unwrap();
+    assert!(
+        output
+            .report
+            .entries
+            .iter()
+            .filter(|entry| entry.path.ends_with(".json"))
+            .all(|entry| entry.format.as_deref() == Some("json"))
+    );
+
+    let options = PeelOptions::new().profile(Profile::Grafana, true);
+    let output = Peeler::with_options(options).peel_str(DASHBOARDS).unwrap();
+    assert!(output.text.contains(
+        "  nodes.json: |\n    {\n      \"id\": 42,\n      \"iteration\": 1690000000,\n      \"panels\": [\n        {\n          \"id\": 2,\n          \"type\": \"graph\"\n        }\n      ],\n      \"title\": \"Nodes\",\n      \"version\": 7\n    }\n"
+    ));
+    // ConfigMaps without the label are left to the built-in rule
+    assert!(
+        output
+            .text
+            .contains(r#"  nodes.json: "{\"title\":\"Nodes\",\"id\":42}""#)
+    );
+
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[0].format.as_deref(), Some("grafana"));
+    assert_eq!(
+        entries[1].warnings,
+        vec!["invalid GRAFANA in broken.json: the dashboard has no title".to_string()]
+    );
+    // Other files next to the dashboards are no dashboards
+    assert_eq!(entries[2].path, "data[\"README.md\"]");
+    assert_eq!(
+        entries[2].status,
+        EntryStatus::Skipped {
+            reason: "unsupported file type".to_string()
+        }
+    );
+    assert_eq!(entries[3].format.as_deref(), Some("json"));
+}
+
+#[test]
+fn test_grafana_profile_strips_volatile_fields() {
+    let options = PeelOptions::new()
+        .profile(Profile::Grafana, true)
+        .strip_dashboard_fields(true);
+    let output = Peeler::with_options(options).peel_str(DASHBOARDS).unwrap();
+    assert!(output.text.contains(
+        "  nodes.json: |\n    {\n      \"panels\": [\n        {\n          \"id\": 2,\n          \"type\": \"graph\"\n        }\n      ],\n      \"title\": \"Nodes\"\n    }\n"
+    ));
+
+    let config =
+        Config::from_toml("strip_dashboard_fields = true\n[profiles]\ngrafana = true\n").unwrap();
+    let output = Peeler::with_options(config.apply(PeelOptions::new()))
+        .peel_str(DASHBOARDS)
+        .unwrap();
+    assert!(!output.text.contains("\"iteration\""));
+}
+
+const COMPOSE: &str = r#"services:
+  web:
+    image: web:latest
+    environment:
+      LOG_LEVEL: debug
+      FEATURE_FLAGS: '{"search":true,"beta":["a","b"]}'
+      WORKERS: 4
+  worker:
+    image: worker:latest
+    environment:
+      - MODE=batch
+      - 'QUEUES=["high","low"]'
+configs:
+  nginx:
+    content: "server {\n  listen 80;\n}\n"
+  settings:
+    file: ./settings.json
+"#;
+
+#[test]
+fn test_compose_profile_peels_configs_and_json_environment() {
+    let output = Peeler::new().peel_str(COMPOSE).unwrap();
+    assert!(output.text.contains(
+        "      FEATURE_FLAGS: |\n        {\n          \"beta\": [\n            \"a\",\n            \"b\"\n          ],\n          \"search\": true\n        }\n      WORKERS: 4\n"
+    ));
+    assert!(output.text.contains("      LOG_LEVEL: debug\n"));
+    assert!(output.text.contains(
+        "      - |\n        QUEUES=[\n          \"high\",\n          \"low\"\n        ]\n"
+    ));
+    assert!(output.text.contains("      - MODE=batch\n"));
+    assert!(
+        output
+            .text
+            .contains("    content: |\n      server {\n        listen 80;\n      }\n")
+    );
+
+    let peeled: Vec<(&str, Option<&str>)> = output
+        .report
+        .entries
+        .iter()
+        .filter(|entry| entry.format.is_some())
+        .map(|entry| (entry.path.as_str(), entry.format.as_deref()))
+        .collect();
+    assert_eq!(
+        peeled,
+        vec![
+            ("configs.nginx.content", Some("text")),
+            ("services.web.environment.FEATURE_FLAGS", Some("json")),
+            ("services.worker.environment[1]", Some("json")),
+        ]
+    );
+}
+
+#[test]
+fn test_compose_profile_needs_services_and_can_be_turned_off() {
+    let options = PeelOptions::new().profile(Profile::Compose, false);
+    let output = Peeler::with_options(options).peel_str(COMPOSE).unwrap();
+    assert!(output.report.entries.is_empty());
+
+    // Without `services` at the root the document is no Compose file
+    let output = Peeler::new()
+        .peel_str("configs:\n  nginx:\n    content: \"a\\nb\"\n")
+        .unwrap();
+    assert!(output.report.entries.is_empty());
+}
+
+const CRONJOB: &str = r#"apiVersion: batch/v1
+kind: CronJob
+metadata:
+  name: backup
+spec:
+  jobTemplate:
+    spec:
+      template:
+        spec:
+          initContainers:
+            - name: wait
+              command: ["/bin/sh", "-c", "until nc -z db 5432\ndo sleep 1\ndone"]
+          containers:
+            - name: backup
+              args: ["-c", "printf '%s\\n' hello; sed -e 's/a/\\t/' f"]
+              env:
+                - name: LOG_LEVEL
+                  value: debug
+                - name: PATTERN
+                  value: '^\d+\n$'
+                - name: SETTINGS
+                  value: '{"targets":["s3"],"retention":7}'
+"#;
+
+#[test]
+fn test_workloads_profile_peels_scripts_and_json() {
+    let output = Peeler::new().peel_str(CRONJOB).unwrap();
+    assert!(output.text.contains(
+        "              command:\n                - /bin/sh\n                - -c\n                - |-\n                  until nc -z db 5432\n                  do sleep 1\n                  done\n"
+    ));
+
+    let peeled: Vec<(&str, Option<&str>)> = output
+        .report
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry.status, EntryStatus::Peeled))
+        .map(|entry| (entry.path.as_str(), entry.format.as_deref()))
+        .collect();
+    assert_eq!(
+        peeled,
+        vec![
+            (
+                "spec.jobTemplate.spec.template.spec.initContainers[0].command[2]",
+                Some("text")
+            ),
+            (
+                "spec.jobTemplate.spec.template.spec.containers[0].env[2].value",
+                Some("json")
+            ),
+        ]
+    );
+    assert!(output.text.contains(
+        "                - name: SETTINGS\n                  value: |\n                    {\n                      \"retention\": 7,\n                      \"targets\": [\n                        \"s3\"\n                      ]\n                    }\n"
+    ));
+}
+
+#[test]
+fn test_workloads_profile_keeps_one_line_values_byte_for_byte() {
+    let mut document: Value = serde_yaml::from_str(CRONJOB).unwrap();
+    let original = document.clone();
+    let report = Peeler::new().peel_value(&mut document).unwrap();
+    let container = &document["spec"]["jobTemplate"]["spec"]["template"]["spec"]["containers"][0];
+    let expected = &original["spec"]["jobTemplate"]["spec"]["template"]["spec"]["containers"][0];
+    assert_eq!(container["args"], expected["args"]);
+    assert_eq!(container["env"][0], expected["env"][0]);
+    assert_eq!(container["env"][1], expected["env"][1]);
+    assert_eq!(
+        container["args"][1].as_str(),
+        Some(r"printf '%s\n' hello; sed -e 's/a/\t/' f")
+    );
+    assert_eq!(container["env"][1]["value"].as_str(), Some(r"^\d+\n$"));
+    assert!(report.entries.iter().any(|entry| {
+        entry.status
+            == EntryStatus::Skipped {
+                reason: "single-line text".to_string(),
+            }
+    }));
+
+    // Written in place, the file does not change at all
+    let deployment = r#"apiVersion: apps/v1
+kind: Deployment
+spec:
+  template:
+    spec:
+      containers:
+        - args: ["-c", "printf '%s\\n' hello; sed -e 's/a/\\t/' f"]
+          env:
+            - name: PATTERN
+              value: '^\d+\n$'
+"#;
+    let output = Peeler::with_options(PeelOptions::new().template(true))
+        .peel_str(deployment)
+        .unwrap();
+    assert_eq!(output.text, deployment);
+}
+
+#[test]
+fn test_workloads_profile_matches_pod_templates_only() {
+    let deployment = CRONJOB
+        .replace("kind: CronJob", "kind: Deployment")
+        .replace("  jobTemplate:\n    spec:\n", "  x:\n    y:\n");
+    let output = Peeler::new().peel_str(&deployment).unwrap();
+    assert!(output.report.entries.is_empty());
+
+    let options = PeelOptions::new().profile(Profile::Workloads, false);
+    let output = Peeler::with_options(options).peel_str(CRONJOB).unwrap();
+    assert!(output.report.entries.is_empty());
+}
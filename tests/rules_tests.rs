@@ -0,0 +1,206 @@
+use rsp_cli::config::Config;
+use rsp_cli::diff;
+use rsp_cli::error::RspError;
+use rsp_cli::{EntryStatus, PeelOptions, Peeler, TargetRule};
+use std::fs;
+use std::process::Command;
+
+const MANIFEST: &str = r#"apiVersion: v1
+kind: Secret
+metadata:
+  name: app
+  labels:
+    team: platform
+stringData:
+  settings.json: "{\"a\":\n1}"
+data:
+  token.json: eyJhIjoxfQ==
+---
+apiVersion: helm.toolkit.fluxcd.io/v2
+kind: HelmRelease
+metadata:
+  name: web
+spec:
+  valuesContent: "replicas: 2\nimage: web"
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: app
+data:
+  app.yaml: "a: 1\nb: 2"
+"#;
+
+#[test]
+fn test_rules_select_resources_and_paths() {
+    let options = PeelOptions::new()
+        .rule(
+            TargetRule::new()
+                .kind("Secret")
+                .label("team", "platform")
+                .path("stringData"),
+        )
+        .rule(
+            TargetRule::new()
+                .api_version("helm.toolkit.fluxcd.io/v2")
+                .kind("HelmRelease")
+                .path("spec.valuesContent")
+                .format("yaml"),
+        );
+    let output = Peeler::with_options(options).peel_str(MANIFEST).unwrap();
+
+    assert!(
+        output
+            .text
+            .contains("stringData:\n  settings.json: |-\n    {\"a\":\n    1}\n")
+    );
+    assert!(output.text.contains("data:\n  token.json: eyJhIjoxfQ==\n"));
+    assert!(
+        output
+            .text
+            .contains("  valuesContent: |-\n    replicas: 2\n    image: web\n")
+    );
+    assert!(output.text.contains("  app.yaml: |-\n    a: 1\n    b: 2\n"));
+
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].path, "stringData[\"settings.json\"]");
+    assert_eq!(entries[1].resource, "HelmRelease/web");
+    assert_eq!(entries[1].path, "spec.valuesContent");
+    assert_eq!(entries[1].key, "valuesContent");
+    assert_eq!(entries[1].format.as_deref(), Some("yaml"));
+    assert_eq!(entries[2].path, "data[\"app.yaml\"]");
+}
+
+#[test]
+fn test_rule_matchers_and_precedence() {
+    // A label that does not match leaves the Secret alone
+    let options =
+        PeelOptions::new().rule(TargetRule::new().label("team", "data").path("stringData"));
+    let output = Peeler::with_options(options).peel_str(MANIFEST).unwrap();
+    assert!(
+        output
+            .report
+            .entries
+            .iter()
+            .all(|entry| !entry.path.starts_with("stringData"))
+    );
+
+    // The first rule reaching a value decides its format
+    let options = PeelOptions::new()
+        .rule(
+            TargetRule::new()
+                .name("app")
+                .kind("ConfigMap")
+                .path("data")
+                .format("toml"),
+        )
+        .builtin_rule(true);
+    let output = Peeler::with_options(options).peel_str(MANIFEST).unwrap();
+    assert_eq!(output.report.entries.len(), 1);
+    assert_eq!(output.report.entries[0].format.as_deref(), Some("toml"));
+    assert_eq!(output.report.entries[0].warnings.len(), 1);
+
+    // Without the built-in rule nothing is peeled
+    let options = PeelOptions::new().builtin_rule(false);
+    let output = Peeler::with_options(options).peel_str(MANIFEST).unwrap();
+    assert!(output.report.entries.is_empty());
+
+    // Unknown formats are rejected
+    let options = PeelOptions::new().rule(TargetRule::new().path("data").format("hcl"));
+    let result = Peeler::with_options(options).peel_str(MANIFEST);
+    assert!(matches!(result, Err(RspError::InvalidFormat(_))));
+}
+
+#[test]
+fn test_config_file_rules() {
+    let config = Config::from_toml(
+        r#"
+builtin_rule = false
+
+[[rules]]
+kind = "HelmRelease"
+paths = ["spec.valuesContent"]
+format = "yaml"
+"#,
+    )
+    .unwrap();
+    assert!(!config.builtin_rule);
+
+    let peeler = Peeler::with_options(config.apply(PeelOptions::new()));
+    let output = peeler.peel_str(MANIFEST).unwrap();
+    assert_eq!(output.report.entries.len(), 1);
+    assert_eq!(output.report.entries[0].status, EntryStatus::Peeled);
+    assert_eq!(output.report.entries[0].key, "valuesContent");
+
+    assert!(matches!(
+        Config::from_toml("[[rules]]\nkind = \"Secret\"\n"),
+        Err(RspError::InvalidFormat(_))
+    ));
+    assert!(matches!(
+        Config::from_toml("[[rules]]\npaths = [\"data\"]\nkinds = [\"Secret\"]\n"),
+        Err(RspError::InvalidFormat(_))
+    ));
+}
+
+#[test]
+fn test_rules_apply_to_diffs() {
+    let peeler = Peeler::with_options(
+        PeelOptions::new().rule(TargetRule::new().kind("Secret").path("stringData")),
+    );
+    let new = MANIFEST.replace("\\n1}", "\\n2}");
+
+    let diffs = diff::diff_manifests(&peeler, MANIFEST, &new).unwrap();
+
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].resource, "Secret/app");
+    assert_eq!(diffs[0].key, "settings.json");
+}
+
+#[test]
+fn test_cli_discovers_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join(".rsp.toml"),
+        "[[rules]]\nkind = \"Secret\"\npaths = [\"stringData\"]\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("manifest.yaml"), MANIFEST).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+            "--",
+            "peel",
+            "manifest.yaml",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("  settings.json: |-\n"));
+
+    let broken = dir.path().join("broken.toml");
+    fs::write(&broken, "rules = 1\n").unwrap();
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--quiet",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"),
+            "--",
+            "peel",
+            "manifest.yaml",
+            "--config",
+            broken.to_str().unwrap(),
+        ])
+        .current_dir(dir.path())
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
@@ -0,0 +1,172 @@
+use rsp_cli::error::RspError;
+use rsp_cli::selector::{self, PathSegment, Selector};
+use rsp_cli::{PeelOptions, Peeler, TargetRule};
+use serde_yaml::Value;
+use std::process::Command;
+
+const PIPELINE: &str = r#"jobs:
+  build:
+    steps:
+      - name: CONFIG
+        value: "{\"a\": 1,\n \"b\": [1, 2]}"
+      - name: OTHER
+        value: "x\\ny"
+  test:
+    steps:
+      - name: CONFIG
+        value: "c = 1\nd = 2"
+"odd.key": "one\\ntwo"
+retries: 3
+"#;
+
+fn paths(selector: &str) -> Vec<String> {
+    let document: Value = serde_yaml::from_str(PIPELINE).unwrap();
+    Selector::parse(selector)
+        .unwrap()
+        .select(&document)
+        .iter()
+        .map(|path| selector::display_path(path))
+        .collect()
+}
+
+#[test]
+fn test_selector_steps() {
+    assert_eq!(paths(".retries"), vec!["retries"]);
+    assert_eq!(paths("$.retries"), vec!["retries"]);
+    assert_eq!(paths("retries"), vec!["retries"]);
+    assert_eq!(paths(r#"["odd.key"]"#), vec![r#"["odd.key"]"#]);
+    assert_eq!(
+        paths(".jobs.build.steps[1].value"),
+        vec!["jobs.build.steps[1].value"]
+    );
+    assert_eq!(
+        paths(".jobs.*.steps[0].name"),
+        vec!["jobs.build.steps[0].name", "jobs.test.steps[0].name"]
+    );
+    assert_eq!(
+        paths(r#".jobs[*].steps[?(@.name=="CONFIG")].value"#),
+        vec!["jobs.build.steps[0].value", "jobs.test.steps[0].value"]
+    );
+    assert_eq!(
+        paths(".jobs.build.steps[?(@.name != 'CONFIG')]"),
+        vec!["jobs.build.steps[1]"]
+    );
+    assert!(paths(".jobs.build.steps[5]").is_empty());
+    assert!(paths(".missing.key").is_empty());
+}
+
+#[test]
+fn test_selector_errors() {
+    for invalid in [
+        "",
+        ".",
+        ".jobs[",
+        ".jobs[x]",
+        "[?(@.name==CONFIG)]",
+        ".a..b",
+        "[\"open",
+    ] {
+        assert!(
+            matches!(Selector::parse(invalid), Err(RspError::InvalidFormat(_))),
+            "{invalid} should be rejected"
+        );
+    }
+}
+
+#[test]
+fn test_lookup_follows_concrete_paths() {
+    let document: Value = serde_yaml::from_str(PIPELINE).unwrap();
+    let path = vec![
+        PathSegment::Key("jobs".to_string()),
+        PathSegment::Key("test".to_string()),
+        PathSegment::Key("steps".to_string()),
+        PathSegment::Index(0),
+        PathSegment::Key("name".to_string()),
+    ];
+    assert_eq!(
+        selector::lookup(&document, &path).and_then(Value::as_str),
+        Some("CONFIG")
+    );
+}
+
+#[test]
+fn test_selectors_look_through_tags() {
+    let document: Value =
+        serde_yaml::from_str("items: !List\n  - !Sub \"a\"\n  - b\nvalue: !Sub x\n").unwrap();
+    let selector = Selector::parse("items[*]").unwrap();
+    let found: Vec<Option<&str>> = selector
+        .select(&document)
+        .iter()
+        .map(|path| selector::lookup(&document, path).and_then(Value::as_str))
+        .collect();
+    assert_eq!(found, vec![Some("a"), Some("b")]);
+
+    let mut document = document;
+    let path = vec![PathSegment::Key("value".to_string())];
+    *selector::lookup_mut(&mut document, &path).unwrap() = Value::from("y");
+    assert_eq!(
+        serde_yaml::to_string(&document["value"]).unwrap(),
+        "!Sub y\n"
+    );
+}
+
+#[test]
+fn test_peel_selected_strings_in_plain_yaml() {
+    let options = PeelOptions::new()
+        .rule(
+            TargetRule::new()
+                .path(r#".jobs.build.steps[?(@.name=="CONFIG")].value"#)
+                .format("json"),
+        )
+        .rule(TargetRule::new().path(r#"["odd.key"]"#));
+    let output = Peeler::with_options(options).peel_str(PIPELINE).unwrap();
+
+    assert!(output.text.contains(
+        "      - name: CONFIG\n        value: |-\n          {\"a\": 1,\n           \"b\": [1, 2]}\n"
+    ));
+    assert!(output.text.contains("odd.key: |-\n  one\n  two\n"));
+    // Strings that were not selected keep their escapes
+    assert!(output.text.contains("        value: x\\ny\n"));
+
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].path, "jobs.build.steps[0].value");
+    assert_eq!(entries[0].key, "value");
+    assert_eq!(entries[0].format.as_deref(), Some("json"));
+    assert_eq!(entries[1].format.as_deref(), Some("text"));
+}
+
+#[test]
+fn test_cli_peel_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("pipeline.yaml");
+    std::fs::write(&input, PIPELINE).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--path",
+            r#".jobs.*.steps[?(@.name=="CONFIG")].value"#,
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("        value: |-\n          c = 1\n          d = 2\n"));
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--path",
+            ".jobs[",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(!output.status.success());
+}
@@ -0,0 +1,145 @@
+use rsp_cli::{EntryStatus, PeelOptions, Peeler, RspError};
+use std::process::Command;
+
+const CHART_TEMPLATE: &str = r#"{{- if .Values.config.enabled }}
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: {{ include "app.fullname" . }}
+  labels:
+    {{- include "app.labels" . | nindent 4 }}
+data:
+  # settings for the app
+  config.json: "{\n  \"replicas\": {{ .Values.replicas }},\n  \"name\": \"{{ .Release.Name }}\"\n}"
+  plain: {{ .Values.plain | quote }}
+  {{- with .Values.extra }}
+  extra.yaml: "a: 1\nb: {{ . }}"
+  {{- end }}
+{{- end }}
+"#;
+
+fn template_peeler() -> Peeler {
+    Peeler::with_options(PeelOptions::new().template(true))
+}
+
+#[test]
+fn test_template_peels_values_and_keeps_directives() {
+    let output = template_peeler().peel_str(CHART_TEMPLATE).unwrap();
+    assert_eq!(
+        output.text,
+        r#"{{- if .Values.config.enabled }}
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: {{ include "app.fullname" . }}
+  labels:
+    {{- include "app.labels" . | nindent 4 }}
+data:
+  # settings for the app
+  config.json: |-
+    {
+      "replicas": {{ .Values.replicas }},
+      "name": "{{ .Release.Name }}"
+    }
+  plain: {{ .Values.plain | quote }}
+  {{- with .Values.extra }}
+  extra.yaml: |-
+    a: 1
+    b: {{ . }}
+  {{- end }}
+{{- end }}
+"#
+    );
+
+    let entries = &output.report.entries;
+    assert_eq!(entries.len(), 3);
+    assert_eq!(
+        entries[0].resource,
+        r#"ConfigMap/{{ include "app.fullname" . }}"#
+    );
+    // Values holding directives cannot be valid until rendered
+    assert!(entries.iter().all(|entry| entry.warnings.is_empty()));
+}
+
+#[test]
+fn test_template_without_values_to_peel_is_unchanged() {
+    let content = "{{- if .Values.enabled }}\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: {{ .Release.Name }}-config   # keep\ndata:\n  app.conf: |\n    port = {{ .Values.port }}\n{{- end }}\n";
+    let output = template_peeler().peel_str(content).unwrap();
+    assert_eq!(output.text, content);
+}
+
+#[test]
+fn test_template_values_in_sequences() {
+    let template = r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: {{ .Release.Name }}
+spec:
+  template:
+    spec:
+      containers:
+        - name: app
+          args:
+            - -c
+            - |
+              set -e
+              exec app --port {{ .Values.port }}
+          command: ["/bin/sh", "-c", "echo a\necho b"]
+          env:
+            - name: SETTINGS
+              value: "{\"port\":{{ .Values.port }},\n\"debug\":false}"
+"#;
+
+    let output = template_peeler().peel_str(template).unwrap();
+    assert!(
+        output
+            .report
+            .entries
+            .iter()
+            .all(|entry| !matches!(entry.status, EntryStatus::Failed { .. }))
+    );
+    // Values already written as blocks, or in flow sequences, are left as
+    // they are
+    assert!(output.text.contains(
+        "            - |\n              set -e\n              exec app --port {{ .Values.port }}\n          command: [\"/bin/sh\", \"-c\", \"echo a\\necho b\"]\n"
+    ));
+    assert!(output.text.contains(
+        "            - name: SETTINGS\n              value: |-\n                {\"port\":{{ .Values.port }},\n                \"debug\":false}\n"
+    ));
+}
+
+#[test]
+fn test_template_unclosed_directive() {
+    let content =
+        "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: {{ .Release.Name\ndata: {}\n";
+    match template_peeler().peel_str(content) {
+        Err(RspError::InvalidFormat(message)) => {
+            assert_eq!(message, "unclosed template directive on line 4");
+        }
+        other => panic!("expected an invalid format error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_templates_need_the_template_option() {
+    assert!(matches!(
+        Peeler::new().peel_str(CHART_TEMPLATE),
+        Err(RspError::Yaml(_))
+    ));
+}
+
+#[test]
+fn test_cli_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("configmap.yaml");
+    std::fs::write(&input, CHART_TEMPLATE).unwrap();
+
+    let output = Command::new("cargo")
+        .args(["run", "--", "peel", input.to_str().unwrap(), "--template"])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("{{- if .Values.config.enabled }}\n"));
+    assert!(stdout.contains("  extra.yaml: |-\n    a: 1\n    b: {{ . }}\n"));
+}
@@ -0,0 +1,165 @@
+use rsp_cli::{DocumentFormat, PeelOptions, Peeler, RspError, TargetRule};
+use std::process::Command;
+
+const APP_TOML: &str = r##"# Application settings
+title = "demo"
+
+[server]
+port = 8080 # the listening port
+# pipeline definition, escaped JSON
+config = "{\n  \"steps\": [\"build\", \"test\"]\n}" # from CI
+script = "#!/bin/sh\necho \"$HOME\"\n"
+banner = '''
+already
+readable'''
+motd = "say '''hi'''\nbye"
+
+[[jobs]]
+name = "a"
+values = "replicas: 2\nimage: web"
+"##;
+
+fn toml_peeler() -> Peeler {
+    let rule = TargetRule::new()
+        .path("server.config")
+        .path("server.script")
+        .path("server.banner")
+        .path("server.motd")
+        .path("jobs[*].values");
+    Peeler::with_options(PeelOptions::new().rule(rule))
+}
+
+#[test]
+fn test_toml_strings_become_multi_line_strings() {
+    let output = toml_peeler().peel_str(APP_TOML).unwrap();
+    assert_eq!(
+        output.text,
+        r##"# Application settings
+title = "demo"
+
+[server]
+port = 8080 # the listening port
+# pipeline definition, escaped JSON
+config = '''
+{
+  "steps": ["build", "test"]
+}''' # from CI
+script = '''
+#!/bin/sh
+echo "$HOME"
+'''
+banner = '''
+already
+readable'''
+motd = """
+say '''hi'''
+bye"""
+
+[[jobs]]
+name = "a"
+values = '''
+replicas: 2
+image: web'''
+"##
+    );
+
+    let paths: Vec<&str> = output
+        .report
+        .entries
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "server.config",
+            "server.script",
+            "server.banner",
+            "server.motd",
+            "jobs[0].values"
+        ]
+    );
+}
+
+#[test]
+fn test_toml_values_round_trip() {
+    let output = toml_peeler().peel_str(APP_TOML).unwrap();
+    let peeled: toml::Value = toml::from_str(&output.text).unwrap();
+    assert_eq!(
+        peeled["server"]["config"].as_str(),
+        Some("{\n  \"steps\": [\"build\", \"test\"]\n}")
+    );
+    assert_eq!(peeled["server"]["port"].as_integer(), Some(8080));
+    assert_eq!(
+        peeled["jobs"][0]["values"].as_str(),
+        Some("replicas: 2\nimage: web")
+    );
+}
+
+#[test]
+fn test_toml_detection() {
+    assert_eq!(DocumentFormat::detect(APP_TOML), DocumentFormat::Toml);
+    assert_eq!(
+        DocumentFormat::detect("[[jobs]]\nname = \"a\""),
+        DocumentFormat::Toml
+    );
+    assert_eq!(
+        DocumentFormat::detect("[\"a\", \"b\"]"),
+        DocumentFormat::Json
+    );
+    assert_eq!(
+        DocumentFormat::detect("apiVersion: v1\nkind: ConfigMap"),
+        DocumentFormat::Yaml
+    );
+    assert_eq!(
+        DocumentFormat::for_path("config/app.toml"),
+        Some(DocumentFormat::Toml)
+    );
+    assert_eq!(DocumentFormat::for_path("Makefile"), None);
+}
+
+#[test]
+fn test_toml_converted_to_other_formats() {
+    let options = PeelOptions::new()
+        .rule(TargetRule::new().path("jobs[*].values"))
+        .output_format(DocumentFormat::Yaml);
+    let output = Peeler::with_options(options).peel_str(APP_TOML).unwrap();
+    assert!(
+        output
+            .text
+            .contains("    values: |-\n      replicas: 2\n      image: web\n")
+    );
+
+    let options = PeelOptions::new().output_format(DocumentFormat::Toml);
+    match Peeler::with_options(options).peel_str("apiVersion: v1\nkind: ConfigMap\n") {
+        Err(RspError::InvalidFormat(message)) => {
+            assert_eq!(message, "TOML output needs TOML input");
+        }
+        other => panic!("expected an invalid format error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_cli_toml_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("app.toml");
+    std::fs::write(&input, APP_TOML).unwrap();
+
+    let output = Command::new("cargo")
+        .args([
+            "run",
+            "--",
+            "peel",
+            input.to_str().unwrap(),
+            "--path",
+            "jobs[*].values",
+        ])
+        .output()
+        .expect("Failed to execute command");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("# Application settings\n"));
+    assert!(stdout.contains("values = '''\nreplicas: 2\nimage: web'''\n"));
+    // Strings no rule selects are left alone
+    assert!(stdout.contains("script = \"#!/bin/sh\\necho \\\"$HOME\\\"\\n\"\n"));
+}